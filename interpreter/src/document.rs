@@ -0,0 +1,449 @@
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+
+use crate::lex::tokens::{Token, TokenName};
+use crate::lex::{LexAnalyzer, LexError};
+use crate::module::Imports;
+use crate::span::Span;
+use crate::syntax::ast::{Program, Statement};
+use crate::syntax::fold::Folder;
+use crate::syntax::parser::{ProgramParser, SyntaxError, SyntaxParser};
+use crate::syntax::resolver::SymbolTable;
+
+/// A lexical or syntax error found in a `Document`.
+#[derive(Debug, Clone)]
+pub enum Diagnostic {
+    Lex(LexError),
+    Syntax(SyntaxError),
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Diagnostic::Lex(error) => write!(f, "{}", error),
+            Diagnostic::Syntax(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Diagnostic {
+    pub fn span(&self) -> Span {
+        match self {
+            Diagnostic::Lex(error) => error.span(),
+            Diagnostic::Syntax(error) => error.span(),
+        }
+    }
+}
+
+/// Replaces the bytes of a document's text in `range` with `replacement`.
+#[derive(Debug, Clone)]
+pub struct TextEdit {
+    pub range: Range<usize>,
+    pub replacement: String,
+}
+
+/// The result of `Document::apply_edit`.
+#[derive(Debug)]
+pub struct DocumentUpdate {
+    /// The whole program, after the edit.
+    pub program: Program,
+    /// Indices of the statements that were re-tokenized to apply the edit.
+    pub relexed: Range<usize>,
+    /// Indices of the statements whose diagnostics may have changed: the re-tokenized
+    /// ones, plus any others whose variables were affected by the edit.
+    pub changed: Vec<usize>,
+    /// The current diagnostics of every changed statement. These replace whatever
+    /// diagnostics those statements had before the edit.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// A u program that's kept lexed and parsed as it is edited, for the REPL and editors.
+///
+/// The text is split into statements at each `;`, along with any trivia in front of
+/// them. An edit only re-tokenizes and re-parses the statements it touches, carrying on
+/// until the token stream lines up with an old statement boundary again. Name resolution
+/// only walks the ASTs, so it's redone for the whole document, but the files it imports
+/// are only loaded the first time.
+pub struct Document {
+    text: String,
+    lexer: LexAnalyzer,
+    statements: Vec<DocumentStatement>,
+    symbols: SymbolTable,
+    /// Kept across edits, so that each imported file is only loaded once.
+    imports: Imports,
+}
+
+struct DocumentStatement {
+    /// An empty span at the start of this statement's leading trivia.
+    start: Span,
+    /// The byte offset just past this statement's `;` (or the end of the text).
+    end: usize,
+    tokens: Vec<Token>,
+    lex_errors: Vec<LexError>,
+    ast: Option<Statement>,
+    syntax_errors: Vec<SyntaxError>,
+    resolve_errors: Vec<SyntaxError>,
+}
+
+impl Document {
+    /// A document whose imports are relative to the current directory.
+    pub fn new(text: &str) -> Self {
+        Self::with_imports(text, Imports::default())
+    }
+
+    /// A document whose imports are loaded with `imports`, such as `Imports::for_file` for
+    /// the file it was read from.
+    pub fn with_imports(text: &str, imports: Imports) -> Self {
+        let mut document = Document {
+            text: text.to_string(),
+            lexer: LexAnalyzer::new(),
+            statements: Vec::new(),
+            symbols: SymbolTable::with_imports(imports.clone()),
+            imports,
+        };
+        let (mut statements, _) = document.lex_statements(Span::default(), |_| false);
+        statements.iter_mut().for_each(|statement| statement.parse());
+        document.statements = statements;
+        document.resolve();
+        document
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Assembles every statement that currently parses into a program.
+    pub fn program(&self) -> Program {
+        Program {
+            statements: self
+                .statements
+                .iter()
+                .filter_map(|statement| statement.ast.clone())
+                .collect(),
+        }
+    }
+
+    /// The variables of every statement that currently parses.
+    pub fn symbols(&self) -> &SymbolTable {
+        &self.symbols
+    }
+
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        self.statements
+            .iter()
+            .flat_map(|statement| statement.diagnostics())
+            .collect()
+    }
+
+    /// Applies `edit` to the text, then brings the tokens and ASTs up to date.
+    ///
+    /// Panics if the edit's range is out of bounds or doesn't lie on char boundaries,
+    /// like `String::replace_range`.
+    pub fn apply_edit(&mut self, edit: TextEdit) -> DocumentUpdate {
+        let TextEdit { range, replacement } = edit;
+        let mut first = self
+            .statements
+            .partition_point(|statement| statement.end <= range.start)
+            .min(self.statements.len().saturating_sub(1));
+        // An edit right at the start of a statement might join onto the end of the previous one.
+        if first > 0 && self.statements[first].start.start == range.start {
+            first -= 1;
+        }
+        // A string or comment that failed to terminate was looked for all the way to the end
+        // of the text, so the edit might be what closes it.
+        while first > 0 && !self.statements[first - 1].lex_errors.is_empty() {
+            first -= 1;
+        }
+        // Or one further back, if the edit adds a character that could close it: before
+        // then, it may have been lexed as an unknown token that ends at whitespace.
+        if replacement.contains(['"', '#', '*', '/']) {
+            let lex_failed = |statement: &DocumentStatement| !statement.lex_errors.is_empty();
+            if let Some(index) = self.statements[..first].iter().position(lex_failed) {
+                first = index;
+            }
+        }
+        let from = self
+            .statements
+            .get(first)
+            .map(|statement| statement.start)
+            .unwrap_or_default();
+
+        self.text.replace_range(range.clone(), &replacement);
+        let new_edit_end = range.start + replacement.len();
+        let to_old_offset = |offset: usize| (offset + range.len()).checked_sub(replacement.len());
+
+        // Re-tokenize until a statement ends exactly where an old statement, entirely
+        // after the edit, used to start: everything from there on is unchanged.
+        let mut resync_at = None;
+        let (relexed, next_start) = self.lex_statements(from, |end| {
+            if end < new_edit_end {
+                return false;
+            }
+            let Some(old_end) = to_old_offset(end) else {
+                return false;
+            };
+            if old_end < range.end {
+                return false;
+            }
+            let index = self
+                .statements
+                .partition_point(|statement| statement.start.start < old_end);
+            if self
+                .statements
+                .get(index)
+                .is_some_and(|statement| statement.start.start == old_end)
+            {
+                resync_at = Some(index);
+                return true;
+            }
+            false
+        });
+        let old_range = first..resync_at.unwrap_or(self.statements.len());
+
+        if let Some(resync_at) = resync_at {
+            let shift = Shift::new(self.statements[resync_at].start, next_start);
+            self.statements[resync_at..]
+                .iter_mut()
+                .for_each(|statement| statement.shift(&shift));
+        }
+
+        let relexed_count = relexed.len();
+        self.statements.splice(old_range, relexed);
+        let relexed_range = first..first + relexed_count;
+
+        self.statements[relexed_range.clone()]
+            .iter_mut()
+            .for_each(|statement| statement.parse());
+        let mut changed: Vec<usize> = relexed_range.clone().collect();
+        changed.extend(
+            self.resolve()
+                .into_iter()
+                .filter(|index| !relexed_range.contains(index)),
+        );
+        changed.sort();
+
+        let diagnostics = changed
+            .iter()
+            .flat_map(|index| self.statements[*index].diagnostics())
+            .collect();
+        DocumentUpdate {
+            program: self.program(),
+            relexed: relexed_range,
+            changed,
+            diagnostics,
+        }
+    }
+
+    /// Resolves every statement's variables from scratch, returning the indices of the
+    /// statements whose resolution errors changed.
+    fn resolve(&mut self) -> Vec<usize> {
+        self.symbols = SymbolTable::with_imports(self.imports.clone());
+        let mut all_errors: Vec<Vec<SyntaxError>> = self
+            .statements
+            .iter()
+            .map(|statement| match &statement.ast {
+                Some(ast) => self.symbols.resolve_statement(ast),
+                None => Vec::new(),
+            })
+            .collect();
+        // A `BEGIN;` that's never closed is reported on that statement.
+        for error in self.symbols.unclosed_blocks() {
+            let begin = error.span();
+            let statement = self.statements.iter().position(|statement| {
+                statement.ast.as_ref().map(Statement::span) == Some(begin)
+            });
+            if let Some(index) = statement {
+                all_errors[index].push(error);
+            }
+        }
+        let mut changed = Vec::new();
+        let statements = self.statements.iter_mut().zip(all_errors);
+        for (index, (statement, errors)) in statements.enumerate() {
+            if errors != statement.resolve_errors {
+                statement.resolve_errors = errors;
+                changed.push(index);
+            }
+        }
+        changed
+    }
+
+    /// Splits the text from `from` onwards into statements, stopping early once
+    /// `stop` returns true for the end offset of a statement.
+    ///
+    /// Also returns the position just past the last statement.
+    fn lex_statements(
+        &self,
+        from: Span,
+        mut stop: impl FnMut(usize) -> bool,
+    ) -> (Vec<DocumentStatement>, Span) {
+        let mut statements = Vec::new();
+        let mut tokens = self.lexer.tokens_from(&self.text, from);
+        let mut current = DocumentStatement::new(from);
+        while let Some(result) = tokens.next() {
+            match result {
+                Ok(token) => {
+                    let end_of_statement = token.name == TokenName::Semicolon;
+                    current.tokens.push(token);
+                    if end_of_statement {
+                        current.end = tokens.position().start;
+                        let next = DocumentStatement::new(tokens.position());
+                        statements.push(std::mem::replace(&mut current, next));
+                        if stop(tokens.position().start) {
+                            return (statements, tokens.position());
+                        }
+                    }
+                }
+                Err(error) => current.lex_errors.push(error),
+            }
+        }
+        if !current.tokens.is_empty() || !current.lex_errors.is_empty() {
+            current.end = tokens.position().start;
+            statements.push(current);
+        }
+        (statements, tokens.position())
+    }
+}
+
+impl DocumentStatement {
+    fn new(start: Span) -> Self {
+        DocumentStatement {
+            start,
+            end: start.start,
+            tokens: Vec::new(),
+            lex_errors: Vec::new(),
+            ast: None,
+            syntax_errors: Vec::new(),
+            resolve_errors: Vec::new(),
+        }
+    }
+
+    fn parse(&mut self) {
+        self.ast = None;
+        self.syntax_errors.clear();
+        // Like the `u` binary, don't bother parsing input that didn't lex.
+        if !self.lex_errors.is_empty() || self.tokens.iter().all(|t| t.name.is_trivia()) {
+            return;
+        }
+        let mut tokens = self.tokens.iter().peekable();
+        match ProgramParser::new().parse(&mut tokens) {
+            Ok(mut program) => self.ast = program.statements.pop(),
+            Err(errors) => self.syntax_errors = errors,
+        }
+    }
+
+    fn diagnostics(&self) -> Vec<Diagnostic> {
+        let mut diagnostics: Vec<Diagnostic> = self
+            .lex_errors
+            .iter()
+            .cloned()
+            .map(Diagnostic::Lex)
+            .chain(self.syntax_errors.iter().cloned().map(Diagnostic::Syntax))
+            .chain(self.resolve_errors.iter().cloned().map(Diagnostic::Syntax))
+            .collect();
+        diagnostics.sort_by_key(|diagnostic| diagnostic.span().start);
+        diagnostics
+    }
+
+    fn shift(&mut self, shift: &Shift) {
+        shift.apply(&mut self.start);
+        self.end = shift.offset(self.end);
+        self.tokens.iter_mut().for_each(|t| shift.apply(&mut t.span));
+        self.lex_errors.iter_mut().for_each(|error| match error {
+            LexError::UnknownToken { span, .. }
+            | LexError::InvalidLiteral { span, .. }
+            | LexError::UnterminatedString { span, .. }
+            | LexError::UnterminatedComment { span }
+            | LexError::UnclosedRepeater { span, .. } => shift.apply(span),
+        });
+        self.ast = self.ast.take().map(|ast| shift.clone().fold_statement(ast));
+        let errors = self.syntax_errors.iter_mut().chain(self.resolve_errors.iter_mut());
+        errors.for_each(|error| match error {
+            SyntaxError::UnexpectedToken { unexpected, .. } => shift.apply(&mut unexpected.span),
+            SyntaxError::InvalidLiteral { span, .. }
+            | SyntaxError::UndeclaredVariable { span, .. }
+            | SyntaxError::AssignedInRepeat { span, .. }
+            | SyntaxError::AssignedInDefinition { span, .. }
+            | SyntaxError::AssignedInConditional { span, .. }
+            | SyntaxError::UndefinedOperator { span, .. }
+            | SyntaxError::UnreachableTarget { span, .. }
+            | SyntaxError::RebindTypeMismatch { span, .. }
+            | SyntaxError::UnmatchedEnd { span }
+            | SyntaxError::UnclosedBlock { span }
+            | SyntaxError::ImportFailed { span, .. }
+            | SyntaxError::QualifiedDefinition { span, .. }
+            | SyntaxError::LineIncomplete { span, .. }
+            | SyntaxError::NestedTooDeeply { span } => shift.apply(span),
+            SyntaxError::AssignedVariableTwice {
+                span,
+                first_assigned: first,
+                ..
+            }
+            | SyntaxError::DefinedOperatorTwice {
+                span,
+                first_defined: first,
+                ..
+            }
+            | SyntaxError::RebindImmutable {
+                span,
+                first_assigned: first,
+                ..
+            }
+            | SyntaxError::ImportedTwice {
+                span,
+                first_imported: first,
+                ..
+            } => {
+                shift.apply(span);
+                shift.apply(first);
+            }
+        });
+    }
+}
+
+/// Moves spans that come after an edit to where they are once the edit is applied.
+#[derive(Debug, Clone)]
+struct Shift {
+    /// Spans starting at or after this offset are moved; earlier ones are left alone.
+    from: usize,
+    bytes: isize,
+    lines: isize,
+    /// Only spans starting on this line (the edit's last line) move sideways.
+    line: usize,
+    columns: isize,
+}
+
+impl Shift {
+    /// The shift that moves the position `old` to `new`.
+    fn new(old: Span, new: Span) -> Self {
+        Shift {
+            from: old.start,
+            bytes: new.start as isize - old.start as isize,
+            lines: new.line as isize - old.line as isize,
+            line: old.line,
+            columns: new.column as isize - old.column as isize,
+        }
+    }
+
+    fn offset(&self, offset: usize) -> usize {
+        offset.saturating_add_signed(self.bytes)
+    }
+
+    fn apply(&self, span: &mut Span) {
+        if span.start < self.from {
+            return;
+        }
+        if span.line == self.line {
+            span.column = span.column.saturating_add_signed(self.columns);
+        }
+        span.line = span.line.saturating_add_signed(self.lines);
+        span.start = self.offset(span.start);
+        span.end = self.offset(span.end);
+    }
+}
+
+impl Folder for Shift {
+    fn fold_span(&mut self, mut span: Span) -> Span {
+        self.apply(&mut span);
+        span
+    }
+}
@@ -0,0 +1,340 @@
+use std::fmt::{Display, Formatter};
+
+use crate::lex::tokens::TokenName;
+use crate::lex::{LexAnalyzer, LexError};
+use crate::syntax::cst::{CstElement, CstKind, CstNode, CstToken};
+use crate::syntax::parser::{ProgramParser, SyntaxError, SyntaxParser};
+
+/// What `format` does with runs of `+` or `-` and the repeaters that could replace them.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum RepeatStyle {
+    /// Leave them as they were written.
+    #[default]
+    Keep,
+    /// `+ + +` becomes `{3} +`.
+    Collapse,
+    /// `{3} +` becomes `+ + +`, and `{-2} +` becomes `- -`, for counts up to
+    /// `MAX_EXPANDED_COUNT`.
+    Expand,
+}
+
+/// The largest repeat count that `RepeatStyle::Expand` writes out in full.
+pub const MAX_EXPANDED_COUNT: u64 = 10;
+
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct FormatOptions {
+    pub repeats: RepeatStyle,
+}
+
+/// Only programs that lex and parse can be formatted.
+#[derive(Debug, Clone)]
+pub enum FormatError {
+    Lex(Vec<LexError>),
+    Syntax(Vec<SyntaxError>),
+}
+
+impl Display for FormatError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FormatError::Lex(_) => write!(f, "Lexical analysis failed!"),
+            FormatError::Syntax(_) => write!(f, "Syntax analysis failed!"),
+        }
+    }
+}
+
+/// Prints `input` in the canonical style: one statement per line, operators separated by
+/// single spaces, and statements inside `BEGIN;` and `END;` indented by two spaces.
+///
+/// Comments are kept where they were, relative to the statements and operators around
+/// them, and so is one blank line wherever there were any. Formatting the output again
+/// doesn't change it.
+pub fn format(input: &str, options: &FormatOptions) -> Result<String, FormatError> {
+    let tokens = LexAnalyzer::new()
+        .lex_analysis(input)
+        .map_err(FormatError::Lex)?;
+    ProgramParser::new()
+        .parse(&mut tokens.iter().peekable())
+        .map_err(FormatError::Syntax)?;
+
+    let mut printer = Printer {
+        options: *options,
+        lines: Vec::new(),
+        depth: 0,
+    };
+    let root = CstNode::parse(input);
+    let mut trivia = Vec::new();
+    for child in &root.children {
+        match child {
+            CstElement::Node(statement) => printer.statement(statement),
+            CstElement::Token(token) => trivia.push(token),
+        }
+    }
+    printer.trivia(&trivia);
+    while printer.lines.last().is_some_and(String::is_empty) {
+        printer.lines.pop();
+    }
+    if printer.lines.is_empty() {
+        return Ok(String::new());
+    }
+    Ok(printer.lines.join("\n") + "\n")
+}
+
+/// One lexeme of the output, which decides the spacing around it.
+#[derive(Debug, PartialEq, Clone)]
+enum Piece {
+    Word(String),
+    IfZero,
+    Open,
+    Pipe,
+    Close,
+    End,
+    Comment(String),
+}
+
+impl Piece {
+    fn text(&self) -> &str {
+        match self {
+            Piece::Word(text) | Piece::Comment(text) => text,
+            Piece::IfZero => "?zero",
+            Piece::Open => "(",
+            Piece::Pipe => "|",
+            Piece::Close => ")",
+            Piece::End => ";",
+        }
+    }
+
+    /// A `#` or `//` comment, which has to end its line.
+    fn is_line_comment(&self) -> bool {
+        matches!(self, Piece::Comment(text) if !text.starts_with("/*"))
+    }
+}
+
+/// Whether to put a space between two pieces on the same line.
+fn spaced(before: &Piece, after: &Piece) -> bool {
+    match (before, after) {
+        (Piece::Open, Piece::Pipe) | (Piece::Pipe, Piece::Close) => true,
+        (Piece::IfZero, Piece::Open) | (Piece::Open, _) => false,
+        (_, Piece::Close) | (_, Piece::End) => false,
+        _ => true,
+    }
+}
+
+struct Printer {
+    options: FormatOptions,
+    lines: Vec<String>,
+    /// How many blocks the next statement is inside.
+    depth: usize,
+}
+
+impl Printer {
+    fn indent(&self) -> String {
+        "  ".repeat(self.depth)
+    }
+
+    fn statement(&mut self, statement: &CstNode) {
+        let start = statement
+            .children
+            .iter()
+            .position(|child| matches!(child, CstElement::Node(_)))
+            .unwrap_or(statement.children.len());
+        let (leading, rest) = statement.children.split_at(start);
+        let leading: Vec<&CstToken> = leading.iter().filter_map(token).collect();
+        self.trivia(&leading);
+
+        let head = rest.first().and_then(node);
+        let keyword = head
+            .filter(|head| head.kind == CstKind::Block)
+            .and_then(|head| head.tokens().first().map(|token| token.name));
+        if keyword == Some(TokenName::End) {
+            self.depth = self.depth.saturating_sub(1);
+        }
+        let mut pieces = Vec::new();
+        if let Some((head, ops)) = rest.split_first() {
+            self.element(head, &mut pieces);
+            self.ops(ops, &mut pieces);
+        }
+        self.write(&pieces);
+        if keyword == Some(TokenName::Begin) {
+            self.depth += 1;
+        }
+    }
+
+    /// Writes the comments in the trivia between two statements. A comment on the same
+    /// line as the end of the statement before stays there, and the rest get lines of
+    /// their own.
+    fn trivia(&mut self, trivia: &[&CstToken]) {
+        let mut newlines = 0;
+        let mut same_line = !self.lines.is_empty();
+        for token in trivia {
+            if token.name == TokenName::Whitespace {
+                newlines += token.text.matches('\n').count();
+                continue;
+            }
+            if same_line && newlines == 0 {
+                let line = self.lines.last_mut().expect("same_line needs a line");
+                line.push(' ');
+                line.push_str(&token.text);
+            } else {
+                self.blank_line(newlines);
+                self.lines.push(self.indent() + &token.text);
+            }
+            same_line = false;
+            newlines = 0;
+        }
+        self.blank_line(newlines);
+    }
+
+    /// Keeps one blank line, if there were any between the last line and the next.
+    fn blank_line(&mut self, newlines: usize) {
+        if newlines > 1 && self.lines.last().is_some_and(|line| !line.is_empty()) {
+            self.lines.push(String::new());
+        }
+    }
+
+    /// Writes one statement, breaking the line only after `#` or `//` comments.
+    fn write(&mut self, pieces: &[Piece]) {
+        let indent = self.indent();
+        let mut line = indent.clone();
+        let mut before: Option<&Piece> = None;
+        for piece in pieces {
+            match before {
+                Some(before) if before.is_line_comment() => {
+                    self.lines.push(line);
+                    line = indent.clone() + "    ";
+                }
+                Some(before) if spaced(before, piece) => line.push(' '),
+                _ => {}
+            }
+            line.push_str(piece.text());
+            before = Some(piece);
+        }
+        self.lines.push(line);
+    }
+
+    fn element(&self, element: &CstElement, pieces: &mut Vec<Piece>) {
+        match element {
+            CstElement::Token(token) => match token.name {
+                TokenName::Whitespace => {}
+                TokenName::Comment => pieces.push(Piece::Comment(token.text.clone())),
+                TokenName::IfZero => pieces.push(Piece::IfZero),
+                TokenName::OpenParen => pieces.push(Piece::Open),
+                TokenName::Pipe => pieces.push(Piece::Pipe),
+                TokenName::CloseParen => pieces.push(Piece::Close),
+                TokenName::Semicolon => pieces.push(Piece::End),
+                // These tokens have spaces of their own, like `AS lib` and `{until 5 max 3}`.
+                TokenName::As | TokenName::Until => {
+                    let words: Vec<&str> = token.text.trim_end_matches('}').split_whitespace().collect();
+                    let close = if token.name == TokenName::Until { "}" } else { "" };
+                    pieces.push(Piece::Word(words.join(" ") + close));
+                }
+                _ => pieces.push(Piece::Word(token.text.clone())),
+            },
+            CstElement::Node(node) if node.kind == CstKind::Group => {
+                let (open, rest) = node.children.split_first().expect("groups start with (");
+                self.element(open, pieces);
+                let close = match rest.last().and_then(token) {
+                    Some(token) if token.name == TokenName::CloseParen => 1,
+                    _ => 0,
+                };
+                let (ops, close) = rest.split_at(rest.len() - close);
+                self.ops(ops, pieces);
+                close.iter().for_each(|close| self.element(close, pieces));
+            }
+            CstElement::Node(node) => {
+                node.children.iter().for_each(|child| self.element(child, pieces));
+            }
+        }
+    }
+
+    /// Writes a run of operators, collapsing or expanding repeats as the options say.
+    fn ops(&self, ops: &[CstElement], pieces: &mut Vec<Piece>) {
+        let mut index = 0;
+        while index < ops.len() {
+            let element = &ops[index];
+            index += 1;
+            match self.options.repeats {
+                RepeatStyle::Collapse => {
+                    if let Some(op) = node(element).and_then(plus_or_minus) {
+                        let mut count = 1;
+                        for (position, next) in ops.iter().enumerate().skip(index) {
+                            match next {
+                                CstElement::Token(token) if token.name == TokenName::Whitespace => {}
+                                CstElement::Node(next) if plus_or_minus(next) == Some(op) => {
+                                    count += 1;
+                                    index = position + 1;
+                                }
+                                _ => break,
+                            }
+                        }
+                        if count > 1 {
+                            pieces.push(Piece::Word(format!("{{{}}}", count)));
+                            pieces.push(Piece::Word(op.to_string()));
+                            continue;
+                        }
+                    }
+                }
+                RepeatStyle::Expand => {
+                    if let Some((count, op)) = node(element).and_then(expandable) {
+                        let inverse = if op == "+" { "-" } else { "+" };
+                        let op = if count < 0 { inverse } else { op };
+                        for _ in 0..count.unsigned_abs() {
+                            pieces.push(Piece::Word(op.to_string()));
+                        }
+                        continue;
+                    }
+                }
+                RepeatStyle::Keep => {}
+            }
+            self.element(element, pieces);
+        }
+    }
+}
+
+fn node(element: &CstElement) -> Option<&CstNode> {
+    match element {
+        CstElement::Node(node) => Some(node),
+        CstElement::Token(_) => None,
+    }
+}
+
+fn token(element: &CstElement) -> Option<&CstToken> {
+    match element {
+        CstElement::Token(token) => Some(token),
+        CstElement::Node(_) => None,
+    }
+}
+
+/// The text of a lone `+` or `-` operator.
+fn plus_or_minus(node: &CstNode) -> Option<&str> {
+    match node.children.as_slice() {
+        [CstElement::Token(token)] if node.kind == CstKind::UnaryOp => match token.name {
+            TokenName::Plus | TokenName::Minus => Some(&token.text),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The count and operator of a repeater like `{3} +`, if `RepeatStyle::Expand` writes it
+/// out. One with a comment inside is left alone, so that the comment stays put.
+fn expandable(node: &CstNode) -> Option<(i64, &str)> {
+    if node.kind != CstKind::RepeatedUnaryOp {
+        return None;
+    }
+    let significant: Vec<&CstElement> = node
+        .children
+        .iter()
+        .filter(|child| !token(child).is_some_and(|token| token.name == TokenName::Whitespace))
+        .collect();
+    let [CstElement::Token(repeater), CstElement::Node(op)] = significant.as_slice() else {
+        return None;
+    };
+    if repeater.name != TokenName::Repeater {
+        return None;
+    }
+    let count: i64 = repeater.text.trim_matches(['{', '}']).parse().ok()?;
+    if count == 0 || count.unsigned_abs() > MAX_EXPANDED_COUNT {
+        return None;
+    }
+    Some((count, plus_or_minus(op)?))
+}
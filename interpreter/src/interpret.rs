@@ -1,14 +1,18 @@
-use crate::lex::tokens::TokenName;
-use crate::syntax::tree::{SyntaxTree, SyntaxTreeKind};
-use std::char::from_digit;
+use crate::module::{qualify, Imports};
+use crate::span::Span;
+use crate::syntax::ast::{Op, Program, RepeatCount, Source, Statement, UntilTarget};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::error::Error;
 use std::fmt::{Display, Formatter};
-use std::ops::{Add, Sub};
+use std::rc::Rc;
 
-#[derive(Debug, Clone)]
+/// Serialized floats are numbers, except for `inf`, `-inf` and `nan`, which are strings.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum UValue {
     Integer(i64),
-    Float(f64),
+    Float(#[serde(with = "crate::serialize::float")] f64),
     Letter(char),
     UString(String),
 }
@@ -24,224 +28,576 @@ impl Display for UValue {
     }
 }
 
-impl Add<i32> for &UValue {
-    type Output = UValue;
-    fn add(self, other: i32) -> Self::Output {
+impl UValue {
+    pub fn utype(&self) -> UType {
         match self {
-            UValue::Integer(int) => UValue::Integer(int + other as i64),
-            UValue::Float(float) => UValue::Float(float + other as f64),
-            UValue::Letter(c) => UValue::Letter(char_add(c, other)),
-            UValue::UString(s) => UValue::UString(s.chars().map(|c| char_add(&c, other)).collect()),
+            UValue::Integer(_) => UType::Integer,
+            UValue::Float(_) => UType::Float,
+            UValue::Letter(_) => UType::Letter,
+            UValue::UString(_) => UType::UString,
         }
     }
-}
 
-impl Sub<i32> for &UValue {
-    type Output = UValue;
-    fn sub(self, other: i32) -> Self::Output {
+    /// Whether `?zero` takes its first branch: for `0`, `0.0`, the first letter of an
+    /// alphabet (like `'a'`, `'A'` or `'0'`) and the empty string.
+    pub fn is_zero(&self) -> bool {
         match self {
-            UValue::Integer(int) => UValue::Integer(int - other as i64),
-            UValue::Float(float) => UValue::Float(float - other as f64),
-            UValue::Letter(c) => UValue::Letter(char_sub(c, other)),
-            UValue::UString(s) => UValue::UString(s.chars().map(|c| char_sub(&c, other)).collect()),
+            UValue::Integer(int) => *int == 0,
+            UValue::Float(float) => *float == 0.0,
+            UValue::Letter(c) => ALPHABETS.iter().any(|alphabet| alphabet.starts_with(*c)),
+            UValue::UString(s) => s.is_empty(),
         }
     }
+
+    /// What `+` (if `by` is `1`) or `-` (if it's `-1`) at `span` turns this value into.
+    /// Letters and digits move `by` places through their alphabet, wrapping around at
+    /// either end, and strings move each of their characters.
+    pub fn shift(&self, by: i64, span: Span) -> Result<UValue, RuntimeError> {
+        match self {
+            UValue::Integer(int) => match int.checked_add(by) {
+                Some(result) => Ok(UValue::Integer(result)),
+                None => Err(RuntimeError::IntegerOverflow {
+                    value: *int,
+                    by,
+                    span,
+                }),
+            },
+            UValue::Float(float) => Ok(UValue::Float(float + by as f64)),
+            UValue::Letter(c) => Ok(UValue::Letter(char_add(*c, by))),
+            UValue::UString(s) => Ok(UValue::UString(s.chars().map(|c| char_add(c, by)).collect())),
+        }
+    }
+}
+
+/// The type of a `UValue`. Operators never change it, so it's known before running.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum UType {
+    Integer,
+    Float,
+    Letter,
+    UString,
 }
 
-fn char_sub(c: &char, other: i32) -> char {
-    if c.is_whitespace() {
-        return c.clone();
+impl Display for UType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UType::Integer => write!(f, "Integer"),
+            UType::Float => write!(f, "Float"),
+            UType::Letter => write!(f, "Letter"),
+            UType::UString => write!(f, "UString"),
+        }
     }
-    let n: i32 = (c.to_digit(36).unwrap() - 10).try_into().unwrap();
-    let decremented = (n - other).rem_euclid(26) + 10;
-    let mut result_char = from_digit(decremented.try_into().unwrap(), 36).unwrap();
-    if c.is_ascii_uppercase() {
-        result_char = result_char.to_ascii_uppercase();
+}
+
+/// Each alphabet that `+` and `-` cycle through, in order. Lowercase and
+/// uppercase letters are separate alphabets so that case is preserved, and digits
+/// are one too, so that `'9' +` is `'0'`.
+const ALPHABETS: [&str; 7] = [
+    "0123456789",
+    "abcdefghijklmnopqrstuvwxyz",
+    "ABCDEFGHIJKLMNOPQRSTUVWXYZ",
+    "αβγδεζηθικλμνξοπρστυφχψω",
+    "ΑΒΓΔΕΖΗΘΙΚΛΜΝΞΟΠΡΣΤΥΦΧΨΩ",
+    "абвгдежзийклмнопрстуфхцчшщъыьэюя",
+    "АБВГДЕЖЗИЙКЛМНОПРСТУФХЦЧШЩЪЫЬЭЮЯ",
+];
+
+/// Moves `c` `by` places through its alphabet. Any other character, like whitespace,
+/// punctuation, emoji or letters outside the alphabets above (like 'ß', or CJK), has no
+/// successor and is left unchanged.
+///
+/// The final sigma `ς` is only another way of writing `σ`, so it moves from `σ`'s place.
+fn char_add(c: char, by: i64) -> char {
+    let c = if c == 'ς' { 'σ' } else { c };
+    for alphabet in ALPHABETS {
+        if let Some(n) = alphabet.chars().position(|letter| letter == c) {
+            let length = alphabet.chars().count() as i64;
+            let shifted = (n as i64 + by.rem_euclid(length)).rem_euclid(length);
+            if let Some(shifted) = alphabet.chars().nth(shifted as usize) {
+                return shifted;
+            }
+        }
     }
-    return result_char;
+    c
+}
+
+/// An error that stops a program part way through running it.
+#[derive(Debug, PartialEq, Clone)]
+pub enum RuntimeError {
+    RepeatCountNotInteger { var_name: String, utype: UType, span: Span },
+    CallDepthExceeded { op_name: String, span: Span },
+    /// Groups, repeaters, `?zero` branches and calls were nested more than
+    /// `MAX_RUN_DEPTH` deep while running.
+    RunDepthExceeded { span: Span },
+    IterationLimitExceeded { max_iterations: u32, span: Span },
+    /// `SET` inside a named operator was given a value of a different type than the
+    /// variable's, which can't be checked before running it.
+    RebindTypeMismatch { var_name: String, declared: UType, utype: UType, span: Span },
+    ImportFailed { path: String, reason: String, span: Span },
+    IntegerOverflow { value: i64, by: i64, span: Span },
+    /// The program uses a variable or named operator it doesn't define, or assigns a
+    /// variable twice. Resolving it with a `SymbolTable` first rules these out.
+    UndefinedVariable { var_name: String, span: Span },
+    UndefinedOperator { op_name: String, span: Span },
+    AssignedTwice { var_name: String, span: Span },
 }
 
-fn char_add(c: &char, other: i32) -> char {
-    if c.is_whitespace() {
-        return c.clone();
+impl Display for RuntimeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuntimeError::RepeatCountNotInteger {
+                var_name,
+                utype,
+                span,
+            } => {
+                write!(
+                    f,
+                    "{}: Repeat count must be an Integer, but {} is a {}",
+                    span, var_name, utype
+                )
+            }
+            RuntimeError::CallDepthExceeded { op_name, span } => {
+                write!(
+                    f,
+                    "{}: Too many nested operator calls (more than {}): {}",
+                    span, MAX_CALL_DEPTH, op_name
+                )
+            }
+            RuntimeError::RunDepthExceeded { span } => {
+                write!(
+                    f,
+                    "{}: Operators nested too deeply while running (more than {} levels)",
+                    span, MAX_RUN_DEPTH
+                )
+            }
+            RuntimeError::IterationLimitExceeded {
+                max_iterations,
+                span,
+            } => {
+                write!(
+                    f,
+                    "{}: Loop did not reach its target within {} iterations",
+                    span, max_iterations
+                )
+            }
+            RuntimeError::ImportFailed { path, reason, span } => {
+                write!(f, "{}: Cannot import \"{}\": {}", span, path, reason)
+            }
+            RuntimeError::RebindTypeMismatch {
+                var_name,
+                declared,
+                utype,
+                span,
+            } => {
+                write!(
+                    f,
+                    "{}: Cannot SET {} to a value of type {}, it was declared with type {}",
+                    span, var_name, utype, declared
+                )
+            }
+            RuntimeError::IntegerOverflow { value, by, span } => {
+                let sign = if *by < 0 { '-' } else { '+' };
+                write!(
+                    f,
+                    "{}: Integer overflow: {} {} {} is out of range",
+                    span,
+                    value,
+                    sign,
+                    by.unsigned_abs()
+                )
+            }
+            RuntimeError::UndefinedVariable { var_name, span } => {
+                write!(f, "{}: Variable not declared: {}", span, var_name)
+            }
+            RuntimeError::UndefinedOperator { op_name, span } => {
+                write!(f, "{}: Operator not defined: {}", span, op_name)
+            }
+            RuntimeError::AssignedTwice { var_name, span } => {
+                write!(f, "{}: Cannot assign to this variable twice: {}", span, var_name)
+            }
+        }
     }
-    let n = c.to_digit(36).unwrap() - 10;
-    let incremented = (n + other as u32) % 26;
-    let mut result_char = from_digit(incremented + 10, 36).unwrap();
-    if c.is_ascii_uppercase() {
-        result_char = result_char.to_ascii_uppercase();
+}
+impl Error for RuntimeError {}
+
+impl RuntimeError {
+    /// The location of the operator that failed.
+    pub fn span(&self) -> Span {
+        match self {
+            RuntimeError::RepeatCountNotInteger { span, .. }
+            | RuntimeError::CallDepthExceeded { span, .. }
+            | RuntimeError::RunDepthExceeded { span }
+            | RuntimeError::IterationLimitExceeded { span, .. }
+            | RuntimeError::RebindTypeMismatch { span, .. }
+            | RuntimeError::ImportFailed { span, .. }
+            | RuntimeError::IntegerOverflow { span, .. }
+            | RuntimeError::UndefinedVariable { span, .. }
+            | RuntimeError::UndefinedOperator { span, .. }
+            | RuntimeError::AssignedTwice { span, .. } => *span,
+        }
     }
-    return result_char;
+}
+
+/// How deeply named operators may call each other (or themselves) before the program
+/// is assumed to never finish.
+pub const MAX_CALL_DEPTH: usize = 1000;
+
+/// How deeply operators may be nested while running, counting every group, repeater,
+/// `?zero` branch and call between the statement and the operator being applied. Calls
+/// can each nest a few levels deep before `MAX_CALL_DEPTH` is reached, but a program that
+/// nests deeper than this inside its calls stops with an error instead.
+pub const MAX_RUN_DEPTH: usize = 16 * MAX_CALL_DEPTH;
+
+/// The stack size to run the interpreter with, which holds `MAX_RUN_DEPTH` nested operators
+/// even in a debug build.
+pub const STACK_SIZE: usize = 256 * 1024 * 1024;
+
+/// A variable's value. It's shared between the block that defined it and the named
+/// operators that use it, so that they see each `SET`.
+type Cell = Rc<RefCell<UValue>>;
+
+/// The variables and named operators defined directly in the program, or in one `BEGIN`
+/// block.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Scope {
+    variables: HashMap<String, Cell>,
+    operators: HashMap<String, Rc<NamedOp>>,
+}
+
+/// A named operator, with the variables and operators that were in scope where it was
+/// defined. Its body sees those, even if it's called from a block that shadows them.
+#[derive(Debug)]
+struct NamedOp {
+    name: String,
+    /// Shared, so that calling the operator doesn't copy it.
+    body: Rc<[Op]>,
+    /// Everything in scope at the definition except the operator itself, which would
+    /// make a cycle.
+    captured: Scope,
 }
 
 pub struct UInterpreter {
-    variable_table: HashMap<String, UValue>,
+    /// The outermost scope first, then one for each open block.
+    scopes: Vec<Scope>,
+    /// The named operators being called, innermost last.
+    frames: Vec<Rc<NamedOp>>,
+    /// How many operators are being applied, each inside the last.
+    depth: usize,
+    /// The variables changed by `SET` during `execute`, with their old values, so that
+    /// they can be put back if it fails.
+    rebinds: Vec<(Cell, UValue)>,
+    imports: Imports,
+}
+
+impl Default for UInterpreter {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl UInterpreter {
+    /// An interpreter whose imports are relative to the current directory.
     pub fn new() -> Self {
+        Self::with_imports(Imports::default())
+    }
+
+    /// Share `imports` with the `SymbolTable` the program was resolved with, so that each
+    /// imported file is only loaded once.
+    pub fn with_imports(imports: Imports) -> Self {
         UInterpreter {
-            variable_table: HashMap::new(),
+            scopes: vec![Scope::default()],
+            frames: Vec::new(),
+            depth: 0,
+            rebinds: Vec::new(),
+            imports,
         }
     }
 
-    pub fn execute(&mut self, program: &SyntaxTree) -> Result<Option<UValue>, ()> {
-        assert!(
-            program.kind == SyntaxTreeKind::ProgramStart,
-            "program SyntaxTree passed to execute must be of type ProgramStart"
-        );
+    /// The variables and operators defined outside any block.
+    pub(crate) fn globals(&self) -> &Scope {
+        &self.scopes[0]
+    }
 
+    /// Runs `program`, returning the value of its last statement.
+    ///
+    /// Operator definitions and blocks have no value. If the program fails part way through,
+    /// none of its variables, operators, blocks or `SET`s are kept.
+    pub fn execute(&mut self, program: &Program) -> Result<Option<UValue>, RuntimeError> {
+        let scopes_before = self.scopes.clone();
+        self.rebinds.clear();
         let mut result = None;
-        program.children.iter().for_each(|line| match &line.kind {
-            SyntaxTreeKind::Statement => {
-                result = Some(self.exec_statement(&line));
-            }
-            other => {
-                let msg = format!(
-                    "Syntax subtree directly below ProgramStart is an unexpected kind: {:?}",
-                    other
-                );
-                panic!("{}", msg);
+        for statement in &program.statements {
+            match self.exec_statement(statement) {
+                Ok(value) => result = value,
+                Err(error) => {
+                    self.frames.clear();
+                    for (cell, value) in self.rebinds.drain(..).rev() {
+                        *cell.borrow_mut() = value;
+                    }
+                    self.scopes = scopes_before;
+                    return Err(error);
+                }
             }
-        });
-        return Ok(result);
+        }
+        Ok(result)
     }
 
-    fn exec_statement(&mut self, statement: &SyntaxTree) -> UValue {
-        self.prevalidate_statement(&statement);
-        let source_value = self.get_source_value(&statement.children[0]);
-
-        let mut result = source_value;
-
-        let operators = &statement.children[1..(statement.children.len() - 1)];
-
-        operators.iter().for_each(|item| match &item.kind {
-            _ => {
-                result = self.apply_operator(&result, item);
+    fn exec_statement(&mut self, statement: &Statement) -> Result<Option<UValue>, RuntimeError> {
+        match statement {
+            Statement::Chain { source, ops, .. } => {
+                let input = self.get_source_value(source)?;
+                self.apply_operators(&input, ops, false).map(Some)
             }
-        });
-        result
-    }
-
-    fn prevalidate_statement(&self, statement: &SyntaxTree) {
-        assert!(
-            statement.kind == SyntaxTreeKind::Statement,
-            "SyntaxTree passed to exec_statement must be of type Statement"
-        );
-        let children = &statement.children;
-        assert!(
-            children.len() >= 2,
-            "Statement syntaxtreenode should have had at least two child nodes"
-        );
-
-        let source = &children[0];
-        let sink = &children[children.len() - 1];
-        assert!(
-            source.kind == SyntaxTreeKind::Source,
-            "Statement syntaxTree's first child should be a Source"
-        );
-        assert!(
-            sink.kind == SyntaxTreeKind::EndOfLine,
-            "Statement syntaxTree's last node should be a EndOfLine"
-        );
-    }
-
-    fn get_source_value(&self, source_node: &SyntaxTree) -> UValue {
-        assert!(
-            source_node.kind == SyntaxTreeKind::Source,
-            "SyntaxTree passed to get_source_value must be of type Source"
-        );
-        match &source_node.token {
-            None => panic!(
-                "Source nodes should always have a token: {:?}",
-                &source_node
-            ),
-            Some(t) => match t.name {
-                TokenName::Integer => {
-                    let val: i64 = t.value.parse().expect("Malformed integer value");
-                    return UValue::Integer(val);
+            Statement::Define { name, ops, .. } => {
+                let mut captured = Scope::default();
+                for scope in &self.scopes {
+                    captured.variables.extend(scope.variables.clone());
+                    captured.operators.extend(scope.operators.clone());
                 }
-                TokenName::Float => {
-                    let val: f64 = t.value.parse().expect("Malformed float value");
-                    return UValue::Float(val);
-                }
-                TokenName::Letter => {
-                    let val: char = t.value.chars().collect::<Vec<char>>()[0].clone();
-                    return UValue::Letter(val);
-                }
-                TokenName::UString => {
-                    let val: String = t.value.to_string();
-                    return UValue::UString(val);
+                let operator = NamedOp {
+                    name: name.clone(),
+                    body: ops.as_slice().into(),
+                    captured,
+                };
+                self.innermost_scope()
+                    .operators
+                    .insert(name.clone(), Rc::new(operator));
+                Ok(None)
+            }
+            Statement::Begin { .. } => {
+                self.scopes.push(Scope::default());
+                Ok(None)
+            }
+            Statement::End { .. } => {
+                if self.scopes.len() > 1 {
+                    self.scopes.pop();
                 }
-                TokenName::Variable => {
-                    let val = self.variable_table.get(&t.value);
-                    if val.is_none() {
-                        panic!("Internal error: variable [{:?}] not found in table (this should have been caught sooner as a syntax error!)",
-                        t);
+                Ok(None)
+            }
+            Statement::Import {
+                path,
+                path_span,
+                alias,
+                ..
+            } => {
+                let failed = |reason: String| RuntimeError::ImportFailed {
+                    path: path.clone(),
+                    reason,
+                    span: *path_span,
+                };
+                let module = self.imports.load(path).map_err(|error| failed(error.to_string()))?;
+                let exports = module.exports().map_err(|error| {
+                    failed(format!("it failed to run\n{}:{}", module.file(), error))
+                })?;
+                let scope = self.innermost_scope();
+                for (name, cell) in exports.variables {
+                    if let Some(name) = qualify(alias, &name) {
+                        scope.variables.insert(name, cell);
                     }
-                    return val.unwrap().clone();
                 }
-                _ => {
-                    panic!("Unexpected token in Source node: {:?}", t);
+                for (name, operator) in exports.operators {
+                    if let Some(name) = qualify(alias, &name) {
+                        scope.operators.insert(name, operator);
+                    }
                 }
-            },
+                Ok(None)
+            }
+        }
+    }
+
+    fn innermost_scope(&mut self) -> &mut Scope {
+        self.scopes.last_mut().expect("the outermost scope is never closed")
+    }
+
+    /// Finds a variable in the operator being called, or in the open blocks
+    /// innermost-first.
+    fn variable(&self, name: &str, span: Span) -> Result<Cell, RuntimeError> {
+        let found = match self.frames.last() {
+            Some(frame) => frame.captured.variables.get(name),
+            None => self.scopes.iter().rev().find_map(|scope| scope.variables.get(name)),
         };
+        found.map(Rc::clone).ok_or_else(|| RuntimeError::UndefinedVariable {
+            var_name: name.to_string(),
+            span,
+        })
     }
 
-    fn apply_operator(&mut self, input: &UValue, operator: &SyntaxTree) -> UValue {
-        match operator.kind {
-            SyntaxTreeKind::RepeatedUnaryOp => {
-                if operator.children.len() != 1 {
-                    panic!(
-                        "RepeatedUnaryOp subtree should have exactly one child: {:?}",
-                        operator
-                    );
-                }
-                let repeated_operator = &operator.children[0];
-                let repeat_count: u32 = operator
-                    .token
-                    .clone()
-                    .expect("Internal error: RepeatedUnaryOp node should have a token")
-                    .value
-                    .parse()
-                    .expect("Internal error: Failed to parse u32 from repeater token");
+    /// Like `variable`, but for named operators. Inside an operator, its own name refers
+    /// to itself.
+    fn operator(&self, name: &str, span: Span) -> Result<Rc<NamedOp>, RuntimeError> {
+        let found = match self.frames.last() {
+            Some(frame) if frame.name == name => Some(frame),
+            Some(frame) => frame.captured.operators.get(name),
+            None => self.scopes.iter().rev().find_map(|scope| scope.operators.get(name)),
+        };
+        found.map(Rc::clone).ok_or_else(|| RuntimeError::UndefinedOperator {
+            op_name: name.to_string(),
+            span,
+        })
+    }
+
+    fn get_source_value(&self, source: &Source) -> Result<UValue, RuntimeError> {
+        match source {
+            Source::Literal(value, _) => Ok(value.clone()),
+            Source::Var(name, span) => Ok(self.variable(name, *span)?.borrow().clone()),
+        }
+    }
+
+    fn get_repeat_count(&self, count: &RepeatCount) -> Result<i64, RuntimeError> {
+        match count {
+            RepeatCount::Literal(count) => Ok(*count),
+            RepeatCount::Var(name, span) => match self.get_source_value(&Source::Var(name.clone(), *span))? {
+                UValue::Integer(count) => Ok(count),
+                other => Err(RuntimeError::RepeatCountNotInteger {
+                    var_name: name.clone(),
+                    utype: other.utype(),
+                    span: *span,
+                }),
+            },
+        }
+    }
+
+    /// Applies `operators` in order, or their inverses in reverse order if `inverse` is set.
+    fn apply_operators(
+        &mut self,
+        input: &UValue,
+        operators: &[Op],
+        inverse: bool,
+    ) -> Result<UValue, RuntimeError> {
+        let mut result = input.clone();
+        if inverse {
+            for operator in operators.iter().rev() {
+                result = self.apply_operator(&result, operator, inverse)?;
+            }
+        } else {
+            for operator in operators {
+                result = self.apply_operator(&result, operator, inverse)?;
+            }
+        }
+        Ok(result)
+    }
+
+    /// Applies `operator`, or its inverse if `inverse` is set: `+` and `-` swap, groups and
+    /// named operators run backwards, and repeat counts flip sign. `?zero` still picks a
+    /// branch by the value it's given, and applies that branch's inverse.
+    fn apply_operator(
+        &mut self,
+        input: &UValue,
+        operator: &Op,
+        inverse: bool,
+    ) -> Result<UValue, RuntimeError> {
+        if self.depth == MAX_RUN_DEPTH {
+            return Err(RuntimeError::RunDepthExceeded {
+                span: operator.span(),
+            });
+        }
+        self.depth += 1;
+        let result = self.apply_nested_operator(input, operator, inverse);
+        self.depth -= 1;
+        result
+    }
+
+    /// Applies `operator` for `apply_operator`, which keeps count of how deeply it's nested.
+    ///
+    /// This matches on the operators itself rather than using a `Visitor`, since it picks
+    /// which children to apply, how often and in which direction.
+    fn apply_nested_operator(
+        &mut self,
+        input: &UValue,
+        operator: &Op,
+        inverse: bool,
+    ) -> Result<UValue, RuntimeError> {
+        let result = match operator {
+            Op::Repeat(repeat_count, repeated_operator, _) => {
+                let repeat_count = self.get_repeat_count(repeat_count)?;
+                let inverse = inverse != (repeat_count < 0);
                 let mut result = input.clone();
-                for _ in 0..repeat_count {
-                    result = self.apply_operator(&result, &repeated_operator);
+                for _ in 0..repeat_count.unsigned_abs() {
+                    result = self.apply_operator(&result, repeated_operator, inverse)?;
                 }
                 result
             }
-            SyntaxTreeKind::UnaryOp => {
-                match &operator.token {
-                    None => panic!("UnaryOp nodes should always have a token: {:?}", &operator),
-                    Some(token) => match token.name {
-                        TokenName::Plus => {
-                            return input + 1;
-                        }
-                        TokenName::Minus => {
-                            return input - 1;
-                        }
-                        TokenName::Stdout => {
-                            println!("{}", input);
-                            return input.clone();
-                        }
-                        TokenName::Variable => {
-                            let key = token.value.clone();
-                            if self.variable_table.contains_key(&key) {
-                                panic!("Trying to re-assign to already-assigned variable [{:?}] (this should have been caught sooner as a syntax error!)", token)
-                            }
-                            self.variable_table.insert(key, input.clone());
-                            return input.clone();
-                        }
-                        _ => {
-                            panic!("Unexpected token in UnaryOp node: {:?}", token);
-                        }
-                    },
+            Op::Until(target, max_iterations, repeated_operator, span) => {
+                let target = match target {
+                    UntilTarget::Literal(value) => Some(value.clone()),
+                    UntilTarget::Var(name, span) => {
+                        Some(self.get_source_value(&Source::Var(name.clone(), *span))?)
+                    }
+                    UntilTarget::FixedPoint => None,
                 };
+                let mut result = input.clone();
+                let mut iterations = 0;
+                loop {
+                    if target.as_ref() == Some(&result) {
+                        break;
+                    }
+                    if iterations == *max_iterations {
+                        return Err(RuntimeError::IterationLimitExceeded {
+                            max_iterations: *max_iterations,
+                            span: *span,
+                        });
+                    }
+                    let next = self.apply_operator(&result, repeated_operator, inverse)?;
+                    iterations += 1;
+                    if target.is_none() && next == result {
+                        break;
+                    }
+                    result = next;
+                }
+                result
             }
-            _ => {
-                panic!("SyntaxTree passed to get_source_value must be of type UnaryOp");
+            Op::Group(operators, _) => self.apply_operators(input, operators, inverse)?,
+            Op::Call(name, span) => {
+                let operator = self.operator(name, *span)?;
+                if self.frames.len() == MAX_CALL_DEPTH {
+                    return Err(RuntimeError::CallDepthExceeded {
+                        op_name: name.clone(),
+                        span: *span,
+                    });
+                }
+                let body = Rc::clone(&operator.body);
+                self.frames.push(operator);
+                let result = self.apply_operators(input, &body, inverse);
+                self.frames.pop();
+                result?
             }
-        }
+            Op::IfZero(if_zero, otherwise, _) => {
+                let branch = if input.is_zero() { if_zero } else { otherwise };
+                self.apply_operators(input, branch, inverse)?
+            }
+            Op::Inc(span) => input.shift(if inverse { -1 } else { 1 }, *span)?,
+            Op::Dec(span) => input.shift(if inverse { 1 } else { -1 }, *span)?,
+            Op::Stdout(_) => {
+                println!("{}", input);
+                input.clone()
+            }
+            Op::Assign(name, span) | Op::AssignMut(name, span) => {
+                let variables = &mut self.innermost_scope().variables;
+                if variables.contains_key(name) {
+                    return Err(RuntimeError::AssignedTwice {
+                        var_name: name.clone(),
+                        span: *span,
+                    });
+                }
+                variables.insert(name.clone(), Rc::new(RefCell::new(input.clone())));
+                input.clone()
+            }
+            Op::Rebind(name, span) => {
+                let cell = self.variable(name, *span)?;
+                let declared = cell.borrow().utype();
+                if declared != input.utype() {
+                    return Err(RuntimeError::RebindTypeMismatch {
+                        var_name: name.clone(),
+                        declared,
+                        utype: input.utype(),
+                        span: *span,
+                    });
+                }
+                let old = cell.replace(input.clone());
+                self.rebinds.push((cell, old));
+                input.clone()
+            }
+        };
+        Ok(result)
     }
 }
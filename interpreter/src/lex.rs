@@ -1,75 +1,316 @@
 use crate::lex::tokens::{Token, TokenName, TokenMatcher};
+use crate::span::Span;
 use std::error::Error;
 use std::fmt::{Display, Formatter};
 
 pub mod tokens;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum LexError {
-    UnknownToken { value: String },
+    UnknownToken { value: String, span: Span, hint: Option<String> },
+    InvalidLiteral { value: String, message: String, span: Span },
+    UnterminatedString { value: String, span: Span },
+    UnterminatedComment { span: Span },
+    UnclosedRepeater { value: String, span: Span },
 }
 
 impl Display for LexError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            LexError::UnknownToken { value } => {
-                write!(f, "Unknown token: {}", value)
+            LexError::UnknownToken { value, span, hint } => {
+                write!(f, "{}: Unknown token: {}", span, value)?;
+                if let Some(hint) = hint {
+                    write!(f, " ({})", hint)?;
+                }
+                Ok(())
+            }
+            LexError::InvalidLiteral {
+                value,
+                message,
+                span,
+            } => {
+                write!(f, "{}: {}: {}", span, message, value)
+            }
+            LexError::UnterminatedString { value, span } => {
+                write!(f, "{}: Unterminated string, missing closing quote: {}", span, value)
+            }
+            LexError::UnterminatedComment { span } => {
+                write!(f, "{}: Unterminated block comment, missing closing */", span)
+            }
+            LexError::UnclosedRepeater { value, span } => {
+                write!(f, "{}: Unclosed repeater, missing closing }}: {}", span, value)
             }
         }
     }
 }
 impl Error for LexError {}
 
+impl LexError {
+    /// The location of the offending input.
+    pub fn span(&self) -> Span {
+        match self {
+            LexError::UnknownToken { span, .. }
+            | LexError::InvalidLiteral { span, .. }
+            | LexError::UnterminatedString { span, .. }
+            | LexError::UnterminatedComment { span }
+            | LexError::UnclosedRepeater { span, .. } => *span,
+        }
+    }
+}
+
 pub struct LexAnalyzer {
     token_matcher: TokenMatcher,
+    precedence: Vec<TokenName>,
+}
+
+impl Default for LexAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl LexAnalyzer {
     pub fn new() -> Self {
         LexAnalyzer {
             token_matcher: TokenMatcher::new(),
+            precedence: Token::all(),
         }
     }
 
-    pub fn lex_analysis(&self, input: &String) -> Result<Vec<Token>, Vec<LexError>> {
-        let tokens = self.collect_tokens(input);
-        let errors: Vec<LexError> = tokens
-            .iter()
-            .filter(|token| token.name == TokenName::Unknown)
-            .map(|token| LexError::UnknownToken {
-                value: token.value.clone(),
-            })
-            .collect();
+    pub fn lex_analysis(&self, input: &str) -> Result<Vec<Token>, Vec<LexError>> {
+        let mut tokens: Vec<Token> = Vec::new();
+        let mut errors: Vec<LexError> = Vec::new();
+        self.tokens(input).for_each(|result| match result {
+            Ok(token) => tokens.push(token),
+            Err(error) => errors.push(error),
+        });
 
         if errors.is_empty() {
-            return Ok(tokens);
+            Ok(tokens)
         } else {
-            return Err(errors);
+            Err(errors)
         }
     }
 
-    fn collect_tokens(&self, input: &String) -> Vec<Token> {
-        let mut tokens = Vec::new();
-        if input.is_empty() {
-            return tokens;
+    /// Lazily tokenizes `input` in a single left-to-right pass.
+    ///
+    /// Unrecognized or malformed input is yielded as an error for that lexeme, and the
+    /// stream carries on with the rest of the input.
+    pub fn tokens<'a>(&'a self, input: &'a str) -> TokenStream<'a> {
+        self.tokens_from(input, Span::default())
+    }
+
+    /// Like `tokens`, but starts lexing part-way through `input`, at `from.start`.
+    ///
+    /// `from.line` and `from.column` must describe that position, since the lexer
+    /// counts lines and columns from there on.
+    pub fn tokens_from<'a>(&'a self, input: &'a str, from: Span) -> TokenStream<'a> {
+        TokenStream {
+            token_matcher: &self.token_matcher,
+            precedence: &self.precedence,
+            input,
+            position: from.start,
+            line: from.line,
+            column: from.column,
         }
-        Token::all().iter().find(|token_kind| {
-            let regex = self.token_matcher.regex(token_kind);
-            let token_match = regex.find(input);
-            if token_match.is_some() {
-                tokens.push(Token {
-                    name: **token_kind,
-                    value: self.token_matcher.pack_value(token_kind, token_match.unwrap().as_str()),
-                });
-                let skip_index = token_match.unwrap().end();
-                let remaining_input = &input[skip_index..].to_string();
-                let mut more_tokens = self.collect_tokens(remaining_input);
-                tokens.append(&mut more_tokens);
-                true
+    }
+}
+
+/// Iterator over the tokens of a borrowed input string; see `LexAnalyzer::tokens`.
+pub struct TokenStream<'a> {
+    token_matcher: &'a TokenMatcher,
+    precedence: &'a [TokenName],
+    input: &'a str,
+    position: usize,
+    line: usize,
+    column: usize,
+}
+
+impl Iterator for TokenStream<'_> {
+    type Item = Result<Token, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let remaining = &self.input[self.position..];
+        let first = remaining.chars().next()?;
+
+        let (token_kind, length) = match self.match_token(remaining, first) {
+            Some(matched) => matched,
+            None => {
+                let length = self.recover(remaining, first);
+                let lexeme = &remaining[..length];
+                let span = self.span_of(length);
+                self.advance(lexeme);
+                return Some(Err(diagnose(lexeme, span)));
+            }
+        };
+        let lexeme = &remaining[..length];
+        let span = self.span_of(length);
+        self.advance(lexeme);
+        let token = match self.token_matcher.pack_value(&token_kind, lexeme) {
+            Ok(value) => Token {
+                name: token_kind,
+                value,
+                span,
+            },
+            Err(message) => {
+                return Some(Err(LexError::InvalidLiteral {
+                    value: lexeme.to_string(),
+                    message,
+                    span,
+                }))
+            }
+        };
+        Some(Ok(token))
+    }
+}
+
+impl TokenStream<'_> {
+    /// An empty span at the point the stream has lexed up to.
+    pub fn position(&self) -> Span {
+        self.span_of(0)
+    }
+
+    /// Finds the highest-precedence token at the start of `remaining`, returning its kind and length.
+    fn match_token(&self, remaining: &str, first: char) -> Option<(TokenName, usize)> {
+        // Only try the token kinds that can start with this character, in order of precedence.
+        self.precedence
+            .iter()
+            .filter(|token_kind| token_kind.may_start_with(first))
+            .find_map(|token_kind| {
+                self.token_matcher
+                    .regex(token_kind)?
+                    .find(remaining)
+                    .map(|token_match| (*token_kind, token_match.end()))
+            })
+    }
+
+    /// Decides how much of `remaining` to report as a single bad lexeme when no token
+    /// matches at its start, so that lexing can resume at the next token boundary.
+    fn recover(&self, remaining: &str, first: char) -> usize {
+        let rest_of_line = remaining.find('\n').unwrap_or(remaining.len());
+        if first == '"' || remaining.starts_with("r\"") || remaining.starts_with("r#\"") {
+            return rest_of_line;
+        }
+        if remaining.starts_with("/*") {
+            return remaining.len();
+        }
+        if remaining.starts_with("{until") {
+            return match remaining[..rest_of_line].find(['}', ';']) {
+                Some(length) if remaining[length..].starts_with('}') => length + 1,
+                Some(length) => length,
+                None => rest_of_line,
+            };
+        }
+        if first == '{' {
+            let length = remaining
+                .find(|c: char| c == '}' || c == ';' || c.is_whitespace())
+                .unwrap_or(remaining.len());
+            if remaining[length..].starts_with('}') {
+                return length + 1;
+            }
+            return length;
+        }
+        if first == '\'' {
+            if let Some(length) = remaining[1..rest_of_line].find('\'') {
+                return length + 2;
+            }
+        }
+
+        // Otherwise, skip ahead to the next character that starts a valid token, without
+        // splitting up a word like `stdout` or `x1`.
+        let in_word = is_word_start(first);
+        let mut length = first.len_utf8();
+        for (index, c) in remaining.char_indices().skip(1) {
+            let continues_word = in_word && is_word_continue(c);
+            if c.is_whitespace() || (!continues_word && self.match_token(&remaining[index..], c).is_some()) {
+                break;
+            }
+            length = index + c.len_utf8();
+        }
+        length
+    }
+
+    fn span_of(&self, length: usize) -> Span {
+        Span {
+            start: self.position,
+            end: self.position + length,
+            line: self.line,
+            column: self.column,
+        }
+    }
+
+    fn advance(&mut self, lexeme: &str) {
+        self.position += lexeme.len();
+        lexeme.chars().for_each(|c| {
+            if c == '\n' {
+                self.line += 1;
+                self.column = 1;
             } else {
-                false
+                self.column += 1;
             }
         });
-        return tokens;
     }
 }
+
+fn is_word_start(c: char) -> bool {
+    c == '_' || c.is_alphabetic()
+}
+
+fn is_word_continue(c: char) -> bool {
+    c == '_' || c.is_alphanumeric()
+}
+
+/// Explains why `lexeme` isn't a valid token, with a suggested fix for common mistakes.
+fn diagnose(lexeme: &str, span: Span) -> LexError {
+    let value = lexeme.to_string();
+    if lexeme.starts_with('"') || lexeme.starts_with("r\"") || lexeme.starts_with("r#\"") {
+        return LexError::UnterminatedString { value, span };
+    }
+    if lexeme.starts_with("/*") {
+        return LexError::UnterminatedComment { span };
+    }
+    if lexeme.starts_with('{') && !lexeme.ends_with('}') {
+        return LexError::UnclosedRepeater { value, span };
+    }
+    if lexeme.starts_with("{until") {
+        let hint = "expected {until $var}, {until number} or {until fixed}, optionally with max n";
+        return LexError::UnknownToken {
+            value,
+            span,
+            hint: Some(String::from(hint)),
+        };
+    }
+
+    let repeat_count = lexeme.strip_prefix('{').and_then(|l| l.strip_suffix('}'));
+    let keyword = ["STDOUT", "BEGIN", "END", "MUT", "SET", "IMPORT", "AS"]
+        .into_iter()
+        .find(|keyword| lexeme.eq_ignore_ascii_case(keyword));
+    let hint = if lexeme == "AS" {
+        Some(String::from("expected a name after AS, like AS lib"))
+    } else if let Some(keyword) = keyword {
+        Some(format!("did you mean {}?", keyword))
+    } else if let Some(count) = repeat_count.filter(|count| count.starts_with(is_word_start)) {
+        Some(format!("did you mean {{${}}}?", count))
+    } else if lexeme == "$" {
+        Some(String::from("variable names must start with a letter or _"))
+    } else if lexeme == "@" {
+        Some(String::from("operator names must start with a letter or _"))
+    } else if lexeme == ":" || lexeme == "=" {
+        Some(String::from("did you mean :=?"))
+    } else if lexeme.starts_with('?') {
+        Some(String::from("did you mean ?zero?"))
+    } else if let Some(inner) = lexeme.strip_prefix('\'').and_then(|l| l.strip_suffix('\'')) {
+        match inner.chars().count() {
+            0 => Some(String::from("letters can't be empty")),
+            _ => Some(format!("letters hold a single character, did you mean \"{}\"?", inner)),
+        }
+    } else if lexeme.starts_with(is_word_start) && lexeme.chars().all(is_word_continue) {
+        match lexeme.chars().count() {
+            1 => Some(format!("did you mean '{}' or ${}?", lexeme, lexeme)),
+            _ => Some(format!("did you mean ${}?", lexeme)),
+        }
+    } else {
+        None
+    };
+    LexError::UnknownToken { value, span, hint }
+}
@@ -1,8 +1,12 @@
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+use crate::span::Span;
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
 pub enum TokenName {
     Whitespace,
+    Comment,
     Float,
     Integer,
     Letter,
@@ -11,15 +15,29 @@ pub enum TokenName {
     Minus,
     Stdout,
     Repeater,
+    Until,
     Variable,
+    OperatorName,
+    Define,
+    OpenParen,
+    CloseParen,
+    IfZero,
+    Pipe,
+    Begin,
+    End,
+    Mut,
+    Set,
+    Import,
+    As,
     Unknown,
     Semicolon,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Token {
     pub name: TokenName,
     pub value: String,
+    pub span: Span,
 }
 
 impl Token {
@@ -27,97 +45,391 @@ impl Token {
     pub fn all() -> Vec<TokenName> {
         vec![
             TokenName::Whitespace,
+            TokenName::Comment,
             TokenName::Repeater,
+            TokenName::Until,
             TokenName::Float,
             TokenName::Integer,
             TokenName::Stdout,
+            TokenName::Begin,
+            TokenName::End,
+            TokenName::Mut,
+            TokenName::Set,
+            TokenName::Import,
+            TokenName::As,
             TokenName::UString,
             TokenName::Plus,
             TokenName::Minus,
             TokenName::Variable,
+            TokenName::OperatorName,
+            TokenName::Define,
             TokenName::Letter,
+            TokenName::OpenParen,
+            TokenName::CloseParen,
+            TokenName::IfZero,
+            TokenName::Pipe,
             TokenName::Semicolon,
-            TokenName::Unknown,
         ]
     }
 }
 
+impl TokenName {
+    /// Trivia tokens carry no meaning for the parser, but are kept in the token
+    /// stream so that tooling can still see them.
+    pub fn is_trivia(&self) -> bool {
+        matches!(self, TokenName::Whitespace | TokenName::Comment)
+    }
+
+    /// Cheap pre-filter so the lexer only tries the regexes that could possibly match.
+    pub fn may_start_with(&self, c: char) -> bool {
+        match self {
+            TokenName::Whitespace => c.is_whitespace(),
+            TokenName::Comment => c == '#' || c == '/',
+            TokenName::Float => c == '-' || c == 'i' || c == 'n' || c.is_ascii_digit(),
+            TokenName::Integer => c == '-' || c.is_ascii_digit(),
+            TokenName::Letter => c == '\'',
+            TokenName::UString => c == '"' || c == 'r',
+            TokenName::Plus => c == '+',
+            TokenName::Minus => c == '-',
+            TokenName::Stdout => c == 'S',
+            TokenName::Repeater => c == '{',
+            TokenName::Until => c == '{',
+            TokenName::Variable => c == '$',
+            TokenName::OperatorName => c == '@',
+            TokenName::Define => c == ':',
+            TokenName::OpenParen => c == '(',
+            TokenName::CloseParen => c == ')',
+            TokenName::IfZero => c == '?',
+            TokenName::Pipe => c == '|',
+            TokenName::Begin => c == 'B',
+            TokenName::End => c == 'E',
+            TokenName::Mut => c == 'M',
+            TokenName::Set => c == 'S',
+            TokenName::Import => c == 'I',
+            TokenName::As => c == 'A',
+            TokenName::Semicolon => c == ';',
+            TokenName::Unknown => false,
+        }
+    }
+
+    /// How this kind of token is described in error messages.
+    pub fn describe(&self) -> &'static str {
+        match self {
+            TokenName::Whitespace => "whitespace",
+            TokenName::Comment => "a comment",
+            TokenName::Float => "a float",
+            TokenName::Integer => "an integer",
+            TokenName::Letter => "a letter",
+            TokenName::UString => "a string",
+            TokenName::Plus => "+",
+            TokenName::Minus => "-",
+            TokenName::Stdout => "STDOUT",
+            TokenName::Repeater => "{n}",
+            TokenName::Until => "{until x}",
+            TokenName::Variable => "$var",
+            TokenName::OperatorName => "@op",
+            TokenName::Define => ":=",
+            TokenName::OpenParen => "(",
+            TokenName::CloseParen => ")",
+            TokenName::IfZero => "?zero",
+            TokenName::Pipe => "|",
+            TokenName::Begin => "BEGIN",
+            TokenName::End => "END",
+            TokenName::Mut => "MUT",
+            TokenName::Set => "SET",
+            TokenName::Import => "IMPORT",
+            TokenName::As => "AS name",
+            TokenName::Unknown => "an unknown token",
+            TokenName::Semicolon => ";",
+        }
+    }
+}
+
+/// A variable or operator name, after its `$` or `@`, or the name an import is given.
+const NAME: &str = r"[\p{XID_Start}_]\p{XID_Continue}*";
+
+/// A name, or an import's name and a name from the imported file, as in `$lib.name`.
+const QUALIFIED_NAME: &str = r"[\p{XID_Start}_]\p{XID_Continue}*(?:\.[\p{XID_Start}_]\p{XID_Continue}*)?";
+
 pub struct TokenMatcher {
     whitespace_regex: Regex,
+    comment_regex: Regex,
     float_regex: Regex,
     integer_regex: Regex,
     letter_regex: Regex,
     repeater_regex: Regex,
+    until_regex: Regex,
     string_regex: Regex,
     variable_regex: Regex,
+    operator_name_regex: Regex,
+    define_regex: Regex,
     plus_regex: Regex,
     minus_regex: Regex,
     stdout_regex: Regex,
+    open_paren_regex: Regex,
+    close_paren_regex: Regex,
+    if_zero_regex: Regex,
+    pipe_regex: Regex,
+    begin_regex: Regex,
+    end_regex: Regex,
+    mut_regex: Regex,
+    set_regex: Regex,
+    import_regex: Regex,
+    as_regex: Regex,
     semicolon_regex: Regex,
-    unknown_regex: Regex,
+}
+
+impl Default for TokenMatcher {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl TokenMatcher {
     pub fn new() -> Self {
         TokenMatcher {
             whitespace_regex: Regex::new(r"^\s+").unwrap(),
-            float_regex: Regex::new(r"^-?[0-9]+\.[0-9]+\s*").unwrap(),
-            integer_regex: Regex::new(r"^-?[0-9]+\s*").unwrap(),
-            letter_regex: Regex::new(r"^'[a-z|A-Z]'\s*").unwrap(),
-            repeater_regex: Regex::new(r"^\{[0-9]+\}\s*").unwrap(),
-            string_regex: Regex::new("^\"[^\"]+\"\\s*").unwrap(),
-            variable_regex: Regex::new(r"^\$[a-z|A-Z|_]+\s*").unwrap(),
-            plus_regex: Regex::new(r"^\+\s*").unwrap(),
-            minus_regex: Regex::new(r"^-\s*").unwrap(),
-            stdout_regex: Regex::new(r"^STDOUT\s*").unwrap(),
+            comment_regex: Regex::new(r"^(?:(?:#|//)[^\n]*|/\*(?s:.*?)\*/)").unwrap(),
+            // Numeric literals swallow any trailing digits, lowercase letters, `_` and `.` (and
+            // uppercase letters after `0x`), so that e.g. `0b102`, `0xZZ` or `1.5e` are reported
+            // as one malformed literal by pack_value. A keyword straight after a number, like
+            // the `STDOUT` in `5STDOUT`, is still a token of its own.
+            float_regex: Regex::new(
+                r"^-?(?:[0-9][0-9_]*(?:\.[0-9_]+)?[eE][+-]?[0-9_]*|[0-9][0-9_]*\.[0-9_]+|inf|nan)[0-9a-z_.]*",
+            )
+            .unwrap(),
+            integer_regex: Regex::new(r"^-?(?:0x[0-9A-Za-z_.]*|[0-9][0-9a-z_.]*)").unwrap(),
+            letter_regex: Regex::new(r"^'(?:[^'\\]|\\u\{[^}']*\}|\\.)'").unwrap(),
+            repeater_regex: Regex::new(&format!(r"^\{{(?:-?[0-9]+|\${})\}}", QUALIFIED_NAME)).unwrap(),
+            // The target is a variable, a number or `fixed`, optionally followed by `max` and
+            // an iteration limit. It's all one token, picked apart by the parser.
+            until_regex: Regex::new(&format!(
+                r"^\{{until[ \t]+(?:\${}|-?[0-9A-Za-z_.+]+)(?:[ \t]+max[ \t]+[0-9]+)?[ \t]*\}}",
+                QUALIFIED_NAME
+            ))
+            .unwrap(),
+            string_regex: Regex::new(r##"^(?:"(?:[^"\\]|\\(?s:.))*"|r"[^"]*"|r#"(?s:.*?)"#)"##).unwrap(),
+            variable_regex: Regex::new(&format!(r"^\${}", QUALIFIED_NAME)).unwrap(),
+            operator_name_regex: Regex::new(&format!(r"^@{}", QUALIFIED_NAME)).unwrap(),
+            define_regex: Regex::new(r"^:=").unwrap(),
+            plus_regex: Regex::new(r"^\+").unwrap(),
+            minus_regex: Regex::new(r"^-").unwrap(),
+            stdout_regex: Regex::new(r"^STDOUT").unwrap(),
+            open_paren_regex: Regex::new(r"^\(").unwrap(),
+            close_paren_regex: Regex::new(r"^\)").unwrap(),
+            if_zero_regex: Regex::new(r"^\?zero").unwrap(),
+            pipe_regex: Regex::new(r"^\|").unwrap(),
+            begin_regex: Regex::new(r"^BEGIN\b").unwrap(),
+            end_regex: Regex::new(r"^END\b").unwrap(),
+            mut_regex: Regex::new(r"^MUT\b").unwrap(),
+            set_regex: Regex::new(r"^SET\b").unwrap(),
+            import_regex: Regex::new(r"^IMPORT\b").unwrap(),
+            // The name an import is given is all one token with the `AS`, since it has no
+            // `$` or `@` to set it apart.
+            as_regex: Regex::new(&format!(r"^AS[ \t]+{}", NAME)).unwrap(),
             semicolon_regex: Regex::new(r"^;").unwrap(),
-            unknown_regex: Regex::new(r"^\S+\s*").unwrap(),
         }
     }
 
-    pub fn regex(&self, token_name: &TokenName) -> &Regex {
-        match token_name {
+    /// Unknown tokens have no pattern: they're whatever the lexer couldn't otherwise match.
+    pub fn regex(&self, token_name: &TokenName) -> Option<&Regex> {
+        let regex = match token_name {
             TokenName::Whitespace => &self.whitespace_regex,
+            TokenName::Comment => &self.comment_regex,
             TokenName::Float => &self.float_regex,
             TokenName::Integer => &self.integer_regex,
             TokenName::Letter => &self.letter_regex,
             TokenName::Repeater => &self.repeater_regex,
+            TokenName::Until => &self.until_regex,
             TokenName::UString => &self.string_regex,
             TokenName::Variable => &self.variable_regex,
+            TokenName::OperatorName => &self.operator_name_regex,
+            TokenName::Define => &self.define_regex,
             TokenName::Plus => &self.plus_regex,
             TokenName::Minus => &self.minus_regex,
             TokenName::Stdout => &self.stdout_regex,
+            TokenName::OpenParen => &self.open_paren_regex,
+            TokenName::CloseParen => &self.close_paren_regex,
+            TokenName::IfZero => &self.if_zero_regex,
+            TokenName::Pipe => &self.pipe_regex,
+            TokenName::Begin => &self.begin_regex,
+            TokenName::End => &self.end_regex,
+            TokenName::Mut => &self.mut_regex,
+            TokenName::Set => &self.set_regex,
+            TokenName::Import => &self.import_regex,
+            TokenName::As => &self.as_regex,
             TokenName::Semicolon => &self.semicolon_regex,
-            TokenName::Unknown => &self.unknown_regex,
-        }
+            TokenName::Unknown => return None,
+        };
+        Some(regex)
     }
 
-    pub fn pack_value(&self, token_name: &TokenName, value: &str) -> String {
-        match token_name {
+    /// Converts a matched lexeme into the token's value, e.g. by stripping delimiters.
+    ///
+    /// Fails with a human-readable reason if the lexeme matched a token's regex but
+    /// still isn't a valid literal (such as a string with an unknown escape sequence).
+    pub fn pack_value(&self, token_name: &TokenName, value: &str) -> Result<String, String> {
+        let packed = match token_name {
             TokenName::Whitespace => value.to_string(),
-            TokenName::Float => value.trim().to_string(),
-            TokenName::Integer => value.trim().to_string(),
-            TokenName::Letter => value
-                .trim()
-                .trim_start_matches('\'')
-                .trim_end_matches('\'')
-                .to_string(),
-            TokenName::Repeater => value
-                .trim()
+            TokenName::Comment => value.to_string(),
+            TokenName::Float => {
+                parse_float_literal(value)?;
+                value.to_string()
+            }
+            TokenName::Integer => {
+                parse_integer_literal(value)?;
+                value.to_string()
+            }
+            TokenName::Letter => {
+                let quoted = value.strip_prefix('\'').unwrap_or(value);
+                unescape(quoted.strip_suffix('\'').unwrap_or(quoted))?
+            }
+            TokenName::Repeater | TokenName::Until => value
                 .trim_start_matches('{')
                 .trim_end_matches('}')
                 .to_string(),
-            TokenName::UString => value
-                .trim()
-                .trim_start_matches('\"')
-                .trim_end_matches('\"')
-                .to_string(),
-            TokenName::Variable => value.trim().to_string(),
+            TokenName::UString => {
+                if let Some(raw) = value.strip_prefix("r#\"") {
+                    raw.strip_suffix("\"#").unwrap_or(raw).to_string()
+                } else if let Some(raw) = value.strip_prefix("r\"") {
+                    raw.strip_suffix('"').unwrap_or(raw).to_string()
+                } else {
+                    let quoted = value.strip_prefix('"').unwrap_or(value);
+                    unescape(quoted.strip_suffix('"').unwrap_or(quoted))?
+                }
+            }
+            TokenName::Variable => value.to_string(),
+            TokenName::OperatorName => value.to_string(),
+            TokenName::Define => value.to_string(),
             TokenName::Plus => value.to_string(),
             TokenName::Minus => value.to_string(),
             TokenName::Stdout => value.to_string(),
+            TokenName::OpenParen => value.to_string(),
+            TokenName::CloseParen => value.to_string(),
+            TokenName::IfZero => value.to_string(),
+            TokenName::Pipe => value.to_string(),
+            TokenName::Begin => value.to_string(),
+            TokenName::End => value.to_string(),
+            TokenName::Mut => value.to_string(),
+            TokenName::Set => value.to_string(),
+            TokenName::Import => value.to_string(),
+            TokenName::As => value.trim_start_matches("AS").trim_start().to_string(),
             TokenName::Semicolon => value.to_string(),
             TokenName::Unknown => value.to_string(),
+        };
+        Ok(packed)
+    }
+}
+
+/// Parses an integer literal such as `-42`, `1_000_000`, `0xFF`, `0b1010` or `0o17`.
+pub fn parse_integer_literal(literal: &str) -> Result<i64, String> {
+    let malformed = || String::from("Malformed integer literal");
+    let (sign, unsigned) = match literal.strip_prefix('-') {
+        Some(unsigned) => ("-", unsigned),
+        None => ("", literal),
+    };
+    let (radix, digits) = if let Some(hex) = unsigned.strip_prefix("0x") {
+        (16, hex)
+    } else if let Some(binary) = unsigned.strip_prefix("0b") {
+        (2, binary)
+    } else if let Some(octal) = unsigned.strip_prefix("0o") {
+        (8, octal)
+    } else {
+        (10, unsigned)
+    };
+    let digits = strip_digit_separators(digits).ok_or_else(malformed)?;
+    if !digits.chars().all(|c| c.is_digit(radix)) {
+        return Err(malformed());
+    }
+    i64::from_str_radix(&format!("{}{}", sign, digits), radix)
+        .map_err(|_| String::from("Integer literal out of range"))
+}
+
+/// Parses a float literal such as `1.5`, `-2.5e-3`, `1e6`, `1_000.25`, `inf` or `nan`.
+pub fn parse_float_literal(literal: &str) -> Result<f64, String> {
+    let malformed = || String::from("Malformed float literal");
+    let unsigned = literal.strip_prefix('-').unwrap_or(literal);
+    if unsigned == "inf" || unsigned == "nan" {
+        return literal.parse().map_err(|_| malformed());
+    }
+    let (mantissa, exponent) = match unsigned.find(['e', 'E']) {
+        Some(index) => (&unsigned[..index], Some(&unsigned[index + 1..])),
+        None => (unsigned, None),
+    };
+    let (whole, fraction) = match mantissa.split_once('.') {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (mantissa, None),
+    };
+    let mut normalized = strip_digit_separators(whole).ok_or_else(malformed)?;
+    if let Some(fraction) = fraction {
+        normalized.push('.');
+        normalized.push_str(&strip_digit_separators(fraction).ok_or_else(malformed)?);
+    }
+    if let Some(exponent) = exponent {
+        let (exponent_sign, exponent_digits) = match exponent.strip_prefix(['+', '-']) {
+            Some(digits) => (&exponent[..1], digits),
+            None => ("", exponent),
+        };
+        normalized.push('e');
+        normalized.push_str(exponent_sign);
+        normalized.push_str(&strip_digit_separators(exponent_digits).ok_or_else(malformed)?);
+    }
+    if !normalized.chars().all(|c| c.is_ascii_digit() || "+-.e".contains(c)) {
+        return Err(malformed());
+    }
+    let value: f64 = normalized.parse().map_err(|_| malformed())?;
+    if value.is_infinite() {
+        return Err(String::from("Float literal out of range"));
+    }
+    Ok(if literal.starts_with('-') { -value } else { value })
+}
+
+/// Removes `_` separators from a run of digits, which may not start or end with one.
+fn strip_digit_separators(digits: &str) -> Option<String> {
+    if digits.is_empty() || digits.starts_with('_') || digits.ends_with('_') {
+        return None;
+    }
+    Some(digits.replace('_', ""))
+}
+
+/// Resolves the backslash escapes allowed in letter and (non-raw) string literals.
+fn unescape(value: &str) -> Result<String, String> {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('"') => unescaped.push('"'),
+            Some('\'') => unescaped.push('\''),
+            Some('\\') => unescaped.push('\\'),
+            Some('n') => unescaped.push('\n'),
+            Some('t') => unescaped.push('\t'),
+            Some('u') => unescaped.push(unescape_unicode(&mut chars)?),
+            Some(other) => return Err(format!("Invalid escape sequence '\\{}'", other)),
+            None => return Err(String::from("Incomplete escape sequence")),
+        }
+    }
+    Ok(unescaped)
+}
+
+/// Parses the `{XXXX}` part of a `\u{XXXX}` escape.
+fn unescape_unicode(chars: &mut std::str::Chars<'_>) -> Result<char, String> {
+    let malformed = || String::from("Malformed unicode escape, expected \\u{XXXX}");
+    if chars.next() != Some('{') {
+        return Err(malformed());
+    }
+    let mut hex = String::new();
+    loop {
+        match chars.next() {
+            Some('}') => break,
+            Some(c) if hex.len() < 6 => hex.push(c),
+            _ => return Err(malformed()),
         }
     }
+    let code_point = u32::from_str_radix(&hex, 16).map_err(|_| malformed())?;
+    char::from_u32(code_point)
+        .ok_or_else(|| format!("Invalid unicode escape: \\u{{{}}} is not a unicode scalar value", hex))
 }
@@ -1,3 +1,8 @@
+pub mod document;
+pub mod format;
 pub mod interpret;
 pub mod lex;
+pub mod module;
+pub mod serialize;
+pub mod span;
 pub mod syntax;
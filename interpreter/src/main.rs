@@ -1,15 +1,49 @@
 use std::env;
 use std::fs;
-use std::collections::HashSet;
 use std::process::ExitCode;
+use std::thread;
 
-use u::interpret::{UInterpreter};
+use serde::Serialize;
+
+use u::format::{format, FormatError, FormatOptions, RepeatStyle};
+use u::interpret::{UInterpreter, STACK_SIZE};
 use u::lex::LexAnalyzer;
-use u::syntax::parser::{ProgramParser, SyntaxParser};
+use u::module::Imports;
+use u::serialize::{from_json, from_sexpr, to_json, to_sexpr, DeserializeError};
+use u::syntax::ast::Program;
+use u::syntax::parser::SyntaxError;
+use u::syntax::resolver::{parse_and_resolve_file, SymbolTable};
 
 fn main() -> ExitCode {
-    let filename = infile_from_args();
-    let input = fs::read_to_string(&filename).unwrap();
+    thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(run)
+        .unwrap()
+        .join()
+        .unwrap()
+}
+
+fn run() -> ExitCode {
+    let args: Vec<String> = env::args().collect();
+    if args.get(1).is_some_and(|arg| arg == "fmt") {
+        return fmt(&args[2..]);
+    }
+    let Some((mode, filename)) = mode_from_args(&args[1..]) else {
+        eprintln!("{}", USAGE);
+        return ExitCode::FAILURE;
+    };
+    let read = fs::read_to_string(&filename)
+        .and_then(|input| Ok((input, Imports::for_file(&filename)?)));
+    let (input, imports) = match read {
+        Ok(read) => read,
+        Err(error) => {
+            eprintln!("{}: {}", filename, error);
+            return ExitCode::FAILURE;
+        }
+    };
+    if mode == Mode::LoadAst {
+        return load_ast(&filename, &input, imports);
+    }
 
     let lex_result = LexAnalyzer::new().lex_analysis(&input);
     let tokens = match lex_result {
@@ -17,31 +51,182 @@ fn main() -> ExitCode {
         Err(errors) => {
             eprintln!("Lexical analysis failed!");
             errors.iter().for_each(|msg| {
-                eprintln!("{}", msg);
+                eprintln!("{}:{}", filename, msg);
             });
             return ExitCode::FAILURE;
         }
     };
-    let mut token_iter = tokens.iter().peekable();
-    let syntax_result = ProgramParser::new().parse(&mut HashSet::new(), &mut token_iter);
+    if let Mode::DumpTokens(sexpr) = mode {
+        println!("{}", dump(&tokens, sexpr));
+        return ExitCode::SUCCESS;
+    }
+    let mut symbols = SymbolTable::with_imports(imports.clone());
+    let syntax_result = parse_and_resolve_file(&tokens, &mut symbols);
     let ast = match syntax_result {
         Ok(ast) => ast,
-        Err(errors) => {
-            eprintln!("Syntax analysis failed!");
-            errors.iter().for_each(|msg| {
-                eprintln!("{}", msg);
-            });
+        Err(errors) => return syntax_failed(&filename, &errors),
+    };
+    if let Mode::DumpAst(sexpr) = mode {
+        println!("{}", dump(&ast, sexpr));
+        return ExitCode::SUCCESS;
+    }
+    execute(&filename, &ast, imports)
+}
+
+const USAGE: &str = "usage: u [--dump-tokens | --dump-ast] [--sexpr] hello.u, or u --load-ast hello.json";
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Mode {
+    Run,
+    /// Print the tokens or the syntax tree as JSON, or as an S-expression if `true`,
+    /// instead of running the program.
+    DumpTokens(bool),
+    DumpAst(bool),
+    /// Run a syntax tree printed by `DumpAst`, in either form.
+    LoadAst,
+}
+
+fn mode_from_args(args: &[String]) -> Option<(Mode, String)> {
+    let mut mode = Mode::Run;
+    let mut sexpr = false;
+    let mut filename = None;
+    for arg in args {
+        match arg.as_str() {
+            "--dump-tokens" if mode == Mode::Run => mode = Mode::DumpTokens(false),
+            "--dump-ast" if mode == Mode::Run => mode = Mode::DumpAst(false),
+            "--load-ast" if mode == Mode::Run => mode = Mode::LoadAst,
+            "--sexpr" => sexpr = true,
+            _ if arg.starts_with("--") || filename.is_some() => return None,
+            _ => filename = Some(arg.clone()),
+        }
+    }
+    let mode = match mode {
+        Mode::DumpTokens(_) => Mode::DumpTokens(sexpr),
+        Mode::DumpAst(_) => Mode::DumpAst(sexpr),
+        _ if sexpr => return None,
+        mode => mode,
+    };
+    Some((mode, filename?))
+}
+
+fn dump<T: Serialize>(value: &T, sexpr: bool) -> String {
+    if sexpr {
+        to_sexpr(value)
+    } else {
+        to_json(value)
+    }
+}
+
+fn load_ast(filename: &str, input: &str, imports: Imports) -> ExitCode {
+    let ast_result = if input.trim_start().starts_with('(') {
+        from_sexpr::<Program>(input)
+    } else {
+        from_json::<Program>(input)
+    };
+    let ast = match ast_result {
+        Ok(ast) => ast,
+        Err(error) => {
+            eprintln!("Reading the syntax tree failed!");
+            match error {
+                DeserializeError::Syntax { .. } => eprintln!("{}:{}", filename, error),
+                DeserializeError::Invalid { .. } => eprintln!("{}: {}", filename, error),
+            }
             return ExitCode::FAILURE;
         }
     };
-    let _ = UInterpreter::new().execute(&ast);
-    return ExitCode::SUCCESS;
+    let mut symbols = SymbolTable::with_imports(imports.clone());
+    if let Err(errors) = symbols.resolve(&ast) {
+        return syntax_failed(filename, &errors);
+    }
+    let unclosed = symbols.unclosed_blocks();
+    if !unclosed.is_empty() {
+        return syntax_failed(filename, &unclosed);
+    }
+    execute(filename, &ast, imports)
 }
 
-fn infile_from_args() -> String {
-    let args: Vec<String> = env::args().collect();
-    if args.len() != 2 {
-        panic!("usage: u hello.u");
+fn syntax_failed(filename: &str, errors: &[SyntaxError]) -> ExitCode {
+    eprintln!("Syntax analysis failed!");
+    errors.iter().for_each(|msg| {
+        eprintln!("{}:{}", filename, msg);
+    });
+    ExitCode::FAILURE
+}
+
+fn execute(filename: &str, ast: &Program, imports: Imports) -> ExitCode {
+    if let Err(error) = UInterpreter::with_imports(imports).execute(ast) {
+        eprintln!("Execution failed!");
+        eprintln!("{}:{}", filename, error);
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+const FMT_USAGE: &str = "usage: u fmt [--check] [--collapse | --expand] hello.u...";
+
+/// `u fmt` rewrites each file in the canonical style. With `--check`, it only lists the
+/// files that would change, and fails if there are any.
+fn fmt(args: &[String]) -> ExitCode {
+    let mut check = false;
+    let mut options = FormatOptions::default();
+    let mut filenames = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "--check" => check = true,
+            "--collapse" if options.repeats == RepeatStyle::Keep => {
+                options.repeats = RepeatStyle::Collapse
+            }
+            "--expand" if options.repeats == RepeatStyle::Keep => {
+                options.repeats = RepeatStyle::Expand
+            }
+            _ if arg.starts_with("--") => {
+                eprintln!("{}", FMT_USAGE);
+                return ExitCode::FAILURE;
+            }
+            _ => filenames.push(arg),
+        }
+    }
+    if filenames.is_empty() {
+        eprintln!("{}", FMT_USAGE);
+        return ExitCode::FAILURE;
+    }
+
+    let mut result = ExitCode::SUCCESS;
+    for filename in filenames {
+        let input = match fs::read_to_string(filename) {
+            Ok(input) => input,
+            Err(error) => {
+                eprintln!("{}: {}", filename, error);
+                result = ExitCode::FAILURE;
+                continue;
+            }
+        };
+        let formatted = match format(&input, &options) {
+            Ok(formatted) => formatted,
+            Err(error) => {
+                eprintln!("{}", error);
+                match error {
+                    FormatError::Lex(errors) => errors
+                        .iter()
+                        .for_each(|msg| eprintln!("{}:{}", filename, msg)),
+                    FormatError::Syntax(errors) => errors
+                        .iter()
+                        .for_each(|msg| eprintln!("{}:{}", filename, msg)),
+                }
+                result = ExitCode::FAILURE;
+                continue;
+            }
+        };
+        if formatted == input {
+            continue;
+        }
+        if check {
+            println!("{}", filename);
+            result = ExitCode::FAILURE;
+        } else if let Err(error) = fs::write(filename, formatted) {
+            eprintln!("{}: {}", filename, error);
+            result = ExitCode::FAILURE;
+        }
     }
-    return String::from(&args[1]);
+    result
 }
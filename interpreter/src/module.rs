@@ -0,0 +1,207 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::interpret::{RuntimeError, Scope, UInterpreter};
+use crate::lex::LexAnalyzer;
+use crate::syntax::ast::Program;
+use crate::syntax::resolver::{parse_and_resolve_file, SymbolTable};
+
+/// Why a file named by `IMPORT` couldn't be used.
+#[derive(Debug, PartialEq, Clone)]
+pub enum ImportError {
+    Unreadable {
+        file: String,
+        reason: String,
+    },
+    /// The files that import each other, starting and ending with the same one.
+    Cycle {
+        files: Vec<String>,
+    },
+    LexFailed {
+        file: String,
+        errors: Vec<String>,
+    },
+    SyntaxFailed {
+        file: String,
+        errors: Vec<String>,
+    },
+}
+
+impl Display for ImportError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImportError::Unreadable { reason, .. } => write!(f, "{}", reason),
+            ImportError::Cycle { files } => {
+                write!(f, "it imports itself ({})", files.join(" -> "))
+            }
+            ImportError::LexFailed { file, errors } => {
+                write!(f, "lexical analysis failed")?;
+                errors
+                    .iter()
+                    .try_for_each(|error| write!(f, "\n{}:{}", file, error))
+            }
+            ImportError::SyntaxFailed { file, errors } => {
+                write!(f, "syntax analysis failed")?;
+                errors
+                    .iter()
+                    .try_for_each(|error| write!(f, "\n{}:{}", file, error))
+            }
+        }
+    }
+}
+
+/// A file that's been imported: lexed, parsed and resolved, and run the first time its
+/// variables and operators are needed.
+#[derive(Debug)]
+pub struct Module {
+    /// The path to the file, as it's shown in error messages.
+    file: String,
+    symbols: SymbolTable,
+    program: Program,
+    /// For the imports the file makes itself.
+    imports: Imports,
+    /// Its variables and operators, once it's been run.
+    exports: RefCell<Option<Scope>>,
+}
+
+impl Module {
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    /// The variables and operators the file defines, outside any block.
+    pub fn symbols(&self) -> &SymbolTable {
+        &self.symbols
+    }
+
+    /// Runs the file, unless it's already been run, and returns what it defines.
+    pub(crate) fn exports(&self) -> Result<Scope, RuntimeError> {
+        if let Some(exports) = self.exports.borrow().as_ref() {
+            return Ok(exports.clone());
+        }
+        let mut interpreter = UInterpreter::with_imports(self.imports.clone());
+        interpreter.execute(&self.program)?;
+        let exports = interpreter.globals().clone();
+        *self.exports.borrow_mut() = Some(exports.clone());
+        Ok(exports)
+    }
+}
+
+/// The name an imported variable or operator is given by `IMPORT ... AS alias`: `$x`
+/// becomes `$alias.x`. Names that were themselves imported aren't passed on.
+pub fn qualify(alias: &str, name: &str) -> Option<String> {
+    if name.contains('.') {
+        return None;
+    }
+    let (sigil, rest) = name.split_at(1);
+    Some(format!("{}{}.{}", sigil, alias, rest))
+}
+
+/// Every file imported so far, shared by everything that imports them so that each one
+/// is only loaded and run once.
+#[derive(Debug, Default)]
+struct ModuleLoader {
+    modules: HashMap<PathBuf, Rc<Module>>,
+    /// The files being loaded, outermost first, so that a file importing one of them can
+    /// be caught. Each is canonical, then as shown in error messages.
+    loading: Vec<(PathBuf, String)>,
+}
+
+/// Loads the files named by `IMPORT`, relative to the importing file.
+#[derive(Debug, Clone, Default)]
+pub struct Imports {
+    loader: Rc<RefCell<ModuleLoader>>,
+    /// The importing file's directory, or empty for the current directory.
+    dir: PathBuf,
+}
+
+impl Imports {
+    /// For the program in the file at `path`, which it's an error to import again.
+    pub fn for_file(path: &str) -> io::Result<Self> {
+        let mut loader = ModuleLoader::default();
+        loader
+            .loading
+            .push((fs::canonicalize(path)?, path.to_string()));
+        Ok(Imports {
+            loader: Rc::new(RefCell::new(loader)),
+            dir: parent(Path::new(path)),
+        })
+    }
+
+    /// Loads the file at `path`, or finds it if it's already been loaded.
+    pub fn load(&self, path: &str) -> Result<Rc<Module>, ImportError> {
+        let joined = self.dir.join(path);
+        let file = joined.display().to_string();
+        let unreadable = |error: io::Error| ImportError::Unreadable {
+            file: file.clone(),
+            reason: error.to_string(),
+        };
+        let canonical = fs::canonicalize(&joined).map_err(unreadable)?;
+        {
+            let loader = self.loader.borrow();
+            if let Some(index) = loader.loading.iter().position(|(p, _)| *p == canonical) {
+                let mut files: Vec<String> = loader.loading[index..]
+                    .iter()
+                    .map(|(_, file)| file.clone())
+                    .collect();
+                files.push(file);
+                return Err(ImportError::Cycle { files });
+            }
+            if let Some(module) = loader.modules.get(&canonical) {
+                return Ok(Rc::clone(module));
+            }
+        }
+        let input = fs::read_to_string(&joined).map_err(unreadable)?;
+        let imports = Imports {
+            loader: Rc::clone(&self.loader),
+            dir: parent(&joined),
+        };
+        self.loader
+            .borrow_mut()
+            .loading
+            .push((canonical.clone(), file.clone()));
+        let module = load_module(file, &input, imports);
+        let mut loader = self.loader.borrow_mut();
+        loader.loading.pop();
+        let module = Rc::new(module?);
+        loader.modules.insert(canonical, Rc::clone(&module));
+        Ok(module)
+    }
+}
+
+fn parent(path: &Path) -> PathBuf {
+    path.parent().map(Path::to_path_buf).unwrap_or_default()
+}
+
+fn messages<E: Display>(errors: &[E]) -> Vec<String> {
+    errors.iter().map(ToString::to_string).collect()
+}
+
+fn load_module(file: String, input: &str, imports: Imports) -> Result<Module, ImportError> {
+    let tokens =
+        LexAnalyzer::new()
+            .lex_analysis(input)
+            .map_err(|errors| ImportError::LexFailed {
+                file: file.clone(),
+                errors: messages(&errors),
+            })?;
+    let mut symbols = SymbolTable::with_imports(imports.clone());
+    let program = parse_and_resolve_file(&tokens, &mut symbols).map_err(|errors| {
+        ImportError::SyntaxFailed {
+            file: file.clone(),
+            errors: messages(&errors),
+        }
+    })?;
+    Ok(Module {
+        file,
+        symbols,
+        program,
+        imports,
+        exports: RefCell::new(None),
+    })
+}
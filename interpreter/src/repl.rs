@@ -1,47 +1,79 @@
-use std::collections::HashSet;
+use std::thread;
 
-use u::interpret::{UInterpreter, UValue};
+use rustyline::error::ReadlineError;
+
+use u::interpret::{UInterpreter, UValue, STACK_SIZE};
 use u::lex::LexAnalyzer;
-use u::syntax::parser::{ProgramParser, SyntaxParser};
+use u::module::Imports;
+use u::syntax::resolver::{parse_and_resolve, SymbolTable};
 
 fn main() {
+    thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(run)
+        .unwrap()
+        .join()
+        .unwrap()
+}
+
+fn run() {
     banner();
 
-    let mut rl = rustyline::DefaultEditor::new().unwrap();
-    let mut variables = HashSet::new();
-    let mut syntax_analyzer = ProgramParser::new();
-    let mut interpreter = UInterpreter::new();
+    let mut rl = match rustyline::DefaultEditor::new() {
+        Ok(rl) => rl,
+        Err(error) => {
+            eprintln!("Cannot read input: {}", error);
+            return;
+        }
+    };
+    // Imports are relative to the current directory.
+    let imports = Imports::default();
+    let mut symbols = SymbolTable::with_imports(imports.clone());
+    let mut interpreter = UInterpreter::with_imports(imports);
 
     loop {
-        let program = rl.readline("> ").unwrap();
-        if program.trim() == "exit" {
-            break;
-        }
-        let result = eval(program, &mut syntax_analyzer, &mut variables, &mut interpreter);
-        match result {
-            Ok(output) => {
-                if let Some(output) = output {
-                    println!("{}", output)
-                }
+        let program = match rl.readline("> ") {
+            Ok(program) => program,
+            // Ctrl-C abandons the line, and Ctrl-D closes the REPL like `exit`.
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(error) => {
+                eprintln!("Cannot read input: {}", error);
+                break;
             }
-            Err(err) => {
-                println!("{:?}", err)
+        };
+        match program.trim() {
+            "exit" => break,
+            "vars" => {
+                print_variables(&symbols);
+                continue;
             }
+            _ => {}
+        }
+        // `eval` has already printed any errors.
+        if let Some(output) = eval(program, &mut symbols, &mut interpreter) {
+            println!("{}", output)
         }
     }
 }
 
 fn banner() {
     println!("u!");
-    println!("type 'exit' to close");
+    println!("type 'vars' to list variables, 'exit' to close");
+}
+
+fn print_variables(symbols: &SymbolTable) {
+    symbols.in_scope().for_each(|symbol| match symbol.utype {
+        Some(utype) => println!("{}: {}", symbol.name, utype),
+        None => println!("{}", symbol.name),
+    });
 }
 
 fn eval(
     input: String,
-    syntax_analyzer: &mut ProgramParser,
-    variables: &mut HashSet<String>,
+    symbols: &mut SymbolTable,
     interpreter: &mut UInterpreter
-    ) -> Result<Option<UValue>, ()> {
+    ) -> Option<UValue> {
     let lex_result = LexAnalyzer::new().lex_analysis(&input);
     let tokens = match lex_result {
         Ok(tokens) => tokens,
@@ -50,11 +82,11 @@ fn eval(
             errors.iter().for_each(|msg| {
                 eprintln!("{}", msg);
             });
-            return Err(());
+            return None;
         }
     };
-    let mut token_iter = tokens.iter().peekable();
-    let syntax_result = syntax_analyzer.parse(variables, &mut token_iter);
+    let symbols_before = symbols.clone();
+    let syntax_result = parse_and_resolve(&tokens, symbols);
     let ast = match syntax_result {
         Ok(ast) => ast,
         Err(errors) => {
@@ -62,9 +94,16 @@ fn eval(
             errors.iter().for_each(|msg| {
                 eprintln!("{}", msg);
             });
-            return Err(());
+            return None;
         }
     };
-    let result = interpreter.execute(&ast)?;
-    Ok(result)
+    match interpreter.execute(&ast) {
+        Ok(result) => result,
+        Err(error) => {
+            eprintln!("Execution failed!");
+            eprintln!("{}", error);
+            *symbols = symbols_before;
+            None
+        }
+    }
 }
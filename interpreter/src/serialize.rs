@@ -0,0 +1,413 @@
+use std::fmt::{Display, Formatter};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Number, Value};
+
+use crate::syntax::parser::MAX_NESTING_DEPTH;
+
+/// How wide `to_sexpr` lets a line get before breaking a list over several lines.
+const SEXPR_WIDTH: usize = 100;
+
+/// How deeply `from_json` and `from_sexpr` let arrays and objects (or lists) be nested,
+/// so that reading doesn't run out of stack. Each level of operators takes at most four,
+/// so any program the parser accepts fits.
+pub const MAX_DEPTH: usize = 4 * MAX_NESTING_DEPTH + 16;
+
+/// Why `from_json` or `from_sexpr` couldn't read a value.
+#[derive(Debug, PartialEq, Clone)]
+pub enum DeserializeError {
+    /// The input isn't valid JSON, or isn't a valid S-expression.
+    Syntax {
+        message: String,
+        line: usize,
+        column: usize,
+    },
+    /// The input is well-formed, but isn't a value of the type that was expected.
+    Invalid { message: String },
+}
+
+impl Display for DeserializeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DeserializeError::Syntax {
+                message,
+                line,
+                column,
+            } => write!(f, "{}:{}: {}", line, column, message),
+            DeserializeError::Invalid { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for DeserializeError {}
+
+/// Writes `value` (a `Token`, a `Program` or any part of one, or a `UValue`) as indented
+/// JSON. Enum variants are objects with the variant's name as their only key, like
+/// `{"Inc": {"start": 2, ...}}`, except for variants without fields, which are just
+/// their name.
+pub fn to_json<T: Serialize>(value: &T) -> String {
+    serde_json::to_string_pretty(value).expect("u's types always serialize")
+}
+
+/// Reads a value written by `to_json`.
+pub fn from_json<T: DeserializeOwned>(input: &str) -> Result<T, DeserializeError> {
+    check_json_depth(input)?;
+    // serde_json's own limit is too low for deeply nested programs, so `check_json_depth`
+    // stands in for it.
+    let mut deserializer = serde_json::Deserializer::from_str(input);
+    deserializer.disable_recursion_limit();
+    let value = T::deserialize(&mut deserializer).map_err(json_error)?;
+    deserializer.end().map_err(json_error)?;
+    Ok(value)
+}
+
+fn json_error(error: serde_json::Error) -> DeserializeError {
+    if error.is_data() {
+        return DeserializeError::Invalid {
+            message: error.to_string(),
+        };
+    }
+    let message = error.to_string();
+    let message = match message.rsplit_once(" at line ") {
+        Some((message, _)) => message.to_string(),
+        None => message,
+    };
+    DeserializeError::Syntax {
+        message,
+        line: error.line(),
+        column: error.column(),
+    }
+}
+
+/// Fails at the first array or object in `input` nested more than `MAX_DEPTH` deep.
+fn check_json_depth(input: &str) -> Result<(), DeserializeError> {
+    let (mut depth, mut in_string, mut escaped) = (0, false, false);
+    let (mut line, mut line_start) = (1, 0);
+    for (position, c) in input.char_indices() {
+        match c {
+            _ if in_string => match c {
+                '\\' if !escaped => escaped = true,
+                '"' if !escaped => in_string = false,
+                _ => escaped = false,
+            },
+            '"' => in_string = true,
+            '[' | '{' => {
+                depth += 1;
+                if depth > MAX_DEPTH {
+                    return Err(DeserializeError::Syntax {
+                        message: format!("nested more than {} deep", MAX_DEPTH),
+                        line,
+                        column: input[line_start..position].chars().count() + 1,
+                    });
+                }
+            }
+            ']' | '}' => depth = depth.saturating_sub(1),
+            '\n' => {
+                line += 1;
+                line_start = position + 1;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Writes `value` as an S-expression, with the same structure as `to_json`: objects
+/// become lists of `:key value` pairs, arrays become lists, strings are quoted like in
+/// JSON and `null` is `nil`. For example, `1 +;` is
+///
+/// ```text
+/// (:statements ((:Chain (:source (:Literal ((:Integer 1) (:start 0 :end 1 :line 1 :column 1)))
+///                        :ops ((:Inc (:start 2 :end 3 :line 1 :column 3)))
+///                        :span (:start 0 :end 4 :line 1 :column 1)))))
+/// ```
+pub fn to_sexpr<T: Serialize>(value: &T) -> String {
+    let value = serde_json::to_value(value).expect("u's types always serialize");
+    let mut out = String::new();
+    write_sexpr(&value, 0, &mut out);
+    out
+}
+
+/// Reads a value written by `to_sexpr`. Comments run from `;` to the end of the line.
+pub fn from_sexpr<T: DeserializeOwned>(input: &str) -> Result<T, DeserializeError> {
+    let mut reader = SexprReader {
+        input,
+        position: 0,
+        depth: 0,
+    };
+    let value = reader.value()?;
+    reader.skip_space();
+    if reader.position < input.len() {
+        return Err(reader.error("expected the end of the input after the value"));
+    }
+    serde_json::from_value(value).map_err(|error| DeserializeError::Invalid {
+        message: error.to_string(),
+    })
+}
+
+fn write_sexpr(value: &Value, indent: usize, out: &mut String) {
+    let compact = compact_sexpr(value);
+    if indent + compact.len() <= SEXPR_WIDTH {
+        out.push_str(&compact);
+        return;
+    }
+    let newline = format!("\n{}", " ".repeat(indent + 1));
+    match value {
+        Value::Array(elements) if !elements.is_empty() => {
+            out.push('(');
+            for (index, element) in elements.iter().enumerate() {
+                if index > 0 {
+                    out.push_str(&newline);
+                }
+                write_sexpr(element, indent + 1, out);
+            }
+            out.push(')');
+        }
+        Value::Object(fields) if !fields.is_empty() => {
+            out.push('(');
+            for (index, (key, field)) in fields.iter().enumerate() {
+                if index > 0 {
+                    out.push_str(&newline);
+                }
+                out.push(':');
+                out.push_str(key);
+                out.push(' ');
+                write_sexpr(field, indent + key.len() + 3, out);
+            }
+            out.push(')');
+        }
+        _ => out.push_str(&compact),
+    }
+}
+
+fn compact_sexpr(value: &Value) -> String {
+    match value {
+        Value::Null => "nil".to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => Value::String(s.clone()).to_string(),
+        Value::Array(elements) => {
+            let elements: Vec<String> = elements.iter().map(compact_sexpr).collect();
+            format!("({})", elements.join(" "))
+        }
+        Value::Object(fields) => {
+            let fields: Vec<String> = fields
+                .iter()
+                .map(|(key, field)| format!(":{} {}", key, compact_sexpr(field)))
+                .collect();
+            format!("({})", fields.join(" "))
+        }
+    }
+}
+
+struct SexprReader<'a> {
+    input: &'a str,
+    /// The byte offset of the next character to read.
+    position: usize,
+    /// How many lists the next character is inside.
+    depth: usize,
+}
+
+impl<'a> SexprReader<'a> {
+    fn error(&self, message: &str) -> DeserializeError {
+        let before = &self.input[..self.position];
+        let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+        DeserializeError::Syntax {
+            message: message.to_string(),
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.position..].chars().next()
+    }
+
+    fn skip_space(&mut self) {
+        while let Some(c) = self.peek() {
+            if c == ';' {
+                let rest = &self.input[self.position..];
+                self.position += rest.find('\n').unwrap_or(rest.len());
+            } else if c.is_whitespace() {
+                self.position += c.len_utf8();
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Reads a symbol, a number or a `:key`, up to the next space, parenthesis or quote.
+    fn word(&mut self) -> &'a str {
+        let rest = &self.input[self.position..];
+        let end = rest
+            .find(|c: char| c.is_whitespace() || "()\";".contains(c))
+            .unwrap_or(rest.len());
+        self.position += end;
+        &rest[..end]
+    }
+
+    fn value(&mut self) -> Result<Value, DeserializeError> {
+        self.skip_space();
+        match self.peek() {
+            None => Err(self.error("expected a value, found the end of the input")),
+            Some('(') if self.depth == MAX_DEPTH => {
+                Err(self.error(&format!("nested more than {} deep", MAX_DEPTH)))
+            }
+            Some('(') => {
+                self.position += 1;
+                self.depth += 1;
+                self.skip_space();
+                let value = if self.peek() == Some(':') {
+                    self.fields()
+                } else {
+                    self.elements()
+                };
+                self.depth -= 1;
+                value
+            }
+            Some(')') => Err(self.error("expected a value, found )")),
+            Some('"') => self.string(),
+            Some(':') => Err(self.error("expected a value, found a :key outside a list of them")),
+            Some(_) => self.atom(),
+        }
+    }
+
+    fn elements(&mut self) -> Result<Value, DeserializeError> {
+        let mut elements = Vec::new();
+        loop {
+            self.skip_space();
+            match self.peek() {
+                Some(')') => break,
+                Some(':') => {
+                    return Err(self.error("expected a value, found a :key in a list of values"))
+                }
+                None => return Err(self.error("expected ), found the end of the input")),
+                _ => elements.push(self.value()?),
+            }
+        }
+        self.position += 1;
+        Ok(Value::Array(elements))
+    }
+
+    fn fields(&mut self) -> Result<Value, DeserializeError> {
+        let mut fields = Map::new();
+        loop {
+            self.skip_space();
+            match self.peek() {
+                Some(')') => break,
+                Some(':') => {}
+                None => return Err(self.error("expected :key or ), found the end of the input")),
+                Some(_) => return Err(self.error("expected :key or )")),
+            }
+            let key = self.word()[1..].to_string();
+            if key.is_empty() {
+                return Err(self.error("expected a name after :"));
+            }
+            let field = self.value()?;
+            fields.insert(key, field);
+        }
+        self.position += 1;
+        Ok(Value::Object(fields))
+    }
+
+    fn string(&mut self) -> Result<Value, DeserializeError> {
+        let start = self.position;
+        let mut escaped = false;
+        for (offset, c) in self.input[start + 1..].char_indices() {
+            match c {
+                '\\' if !escaped => escaped = true,
+                '"' if !escaped => {
+                    self.position = start + 1 + offset + 1;
+                    let quoted = &self.input[start..self.position];
+                    return serde_json::from_str(quoted).map_err(|error| {
+                        self.position = start;
+                        self.error(&format!("invalid string: {}", error))
+                    });
+                }
+                _ => escaped = false,
+            }
+        }
+        Err(self.error("unterminated string"))
+    }
+
+    fn atom(&mut self) -> Result<Value, DeserializeError> {
+        let start = self.position;
+        let word = self.word();
+        let value = match word {
+            "nil" => Some(Value::Null),
+            "true" => Some(Value::Bool(true)),
+            "false" => Some(Value::Bool(false)),
+            _ if word.starts_with(|c: char| c.is_ascii_digit() || c == '-') => {
+                if let Ok(int) = word.parse::<i64>() {
+                    Some(Value::Number(int.into()))
+                } else if let Ok(int) = word.parse::<u64>() {
+                    Some(Value::Number(int.into()))
+                } else {
+                    word.parse().ok().and_then(Number::from_f64).map(Value::Number)
+                }
+            }
+            _ => None,
+        };
+        value.ok_or_else(|| {
+            let message = format!("expected a value, found {}", word);
+            self.position = start;
+            self.error(&message)
+        })
+    }
+}
+
+/// Serializes an `f64` as a number when it's finite, and otherwise as the u literal for
+/// it, since JSON has no numbers for `inf`, `-inf` and `nan`.
+pub(crate) mod float {
+    use serde::de::{self, Visitor};
+    use serde::{Deserializer, Serializer};
+    use std::fmt::Formatter;
+
+    pub fn serialize<S: Serializer>(value: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+        if value.is_finite() {
+            serializer.serialize_f64(*value)
+        } else if value.is_nan() {
+            serializer.serialize_str("nan")
+        } else if *value > 0.0 {
+            serializer.serialize_str("inf")
+        } else {
+            serializer.serialize_str("-inf")
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+        deserializer.deserialize_any(FloatVisitor)
+    }
+
+    struct FloatVisitor;
+
+    impl Visitor<'_> for FloatVisitor {
+        type Value = f64;
+
+        fn expecting(&self, f: &mut Formatter) -> std::fmt::Result {
+            write!(f, "a number, \"inf\", \"-inf\" or \"nan\"")
+        }
+
+        fn visit_f64<E: de::Error>(self, value: f64) -> Result<f64, E> {
+            Ok(value)
+        }
+
+        fn visit_i64<E: de::Error>(self, value: i64) -> Result<f64, E> {
+            Ok(value as f64)
+        }
+
+        fn visit_u64<E: de::Error>(self, value: u64) -> Result<f64, E> {
+            Ok(value as f64)
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<f64, E> {
+            match value {
+                "inf" => Ok(f64::INFINITY),
+                "-inf" => Ok(f64::NEG_INFINITY),
+                "nan" => Ok(f64::NAN),
+                _ => Err(E::invalid_value(de::Unexpected::Str(value), &self)),
+            }
+        }
+    }
+}
@@ -1,2 +1,6 @@
+pub mod ast;
+pub mod cst;
+pub mod fold;
 pub mod parser;
-pub mod tree;
+pub mod resolver;
+pub mod visit;
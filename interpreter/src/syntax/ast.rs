@@ -0,0 +1,135 @@
+use serde::{Deserialize, Serialize};
+
+use crate::interpret::UValue;
+use crate::span::Span;
+
+/// A whole u program, as produced by `ProgramParser`.
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct Program {
+    pub statements: Vec<Statement>,
+}
+
+/// One statement, up to and including its terminating `;`.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum Statement {
+    /// A source value, followed by the operators applied to it in order.
+    Chain { source: Source, ops: Vec<Op>, span: Span },
+    /// `@name := ops;` defines a named operator, which applies `ops` in order.
+    Define { name: String, name_span: Span, ops: Vec<Op>, span: Span },
+    /// `BEGIN;` opens a block. Variables and operators defined in it go out of scope at its
+    /// `END;`, and may shadow ones from outside it.
+    Begin { span: Span },
+    /// `END;` closes the innermost block.
+    End { span: Span },
+    /// `IMPORT "path" AS name;` runs another file, and makes its variables and operators
+    /// available as `$name.x` and `@name.x`. The path is relative to the importing file.
+    Import {
+        path: String,
+        path_span: Span,
+        alias: String,
+        alias_span: Span,
+        span: Span,
+    },
+}
+
+impl Statement {
+    /// Covers the whole statement, through its `;`.
+    pub fn span(&self) -> Span {
+        match self {
+            Statement::Chain { span, .. }
+            | Statement::Define { span, .. }
+            | Statement::Begin { span }
+            | Statement::End { span }
+            | Statement::Import { span, .. } => *span,
+        }
+    }
+
+    pub fn ops(&self) -> &[Op] {
+        match self {
+            Statement::Chain { ops, .. } | Statement::Define { ops, .. } => ops,
+            Statement::Begin { .. } | Statement::End { .. } | Statement::Import { .. } => &[],
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum Source {
+    Literal(UValue, Span),
+    /// A variable assigned by an earlier statement.
+    Var(String, Span),
+}
+
+impl Source {
+    pub fn span(&self) -> Span {
+        match self {
+            Source::Literal(_, span) | Source::Var(_, span) => *span,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum Op {
+    /// `+`
+    Inc(Span),
+    /// `-`
+    Dec(Span),
+    /// `STDOUT`
+    Stdout(Span),
+    /// `$name`, storing the value so far in a new variable.
+    Assign(String, Span),
+    /// `MUT $name`, storing the value so far in a new variable that `SET` can change.
+    /// The span covers both tokens.
+    AssignMut(String, Span),
+    /// `SET $name` replaces the value of a variable declared with `MUT`. The span covers
+    /// both tokens.
+    Rebind(String, Span),
+    /// `{n}` applies the operator after it `n` times, or its inverse `-n` times if `n` is negative.
+    /// The span covers both.
+    Repeat(RepeatCount, Box<Op>, Span),
+    /// `{until x}` applies the operator after it until the value reaches `x`, but no more than
+    /// the given number of times. The span covers both.
+    Until(UntilTarget, u32, Box<Op>, Span),
+    /// `( ... )` applies several operators in order, as one. The span covers the parentheses.
+    Group(Vec<Op>, Span),
+    /// `@name`, applying a named operator defined by an earlier statement (or, to recurse,
+    /// by the definition it's in).
+    Call(String, Span),
+    /// `?zero(a | b)` applies the operators `a` if the value is zero, and `b` otherwise.
+    /// The span covers everything through the `)`.
+    IfZero(Vec<Op>, Vec<Op>, Span),
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum RepeatCount {
+    Literal(i64),
+    /// `{$n}` repeats as many times as the integer in a variable, found at runtime.
+    Var(String, Span),
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum UntilTarget {
+    /// `{until 10}`
+    Literal(UValue),
+    /// `{until $x}` stops at the value in a variable, found at runtime.
+    Var(String, Span),
+    /// `{until fixed}` stops once applying the operator no longer changes the value.
+    FixedPoint,
+}
+
+impl Op {
+    pub fn span(&self) -> Span {
+        match self {
+            Op::Inc(span)
+            | Op::Dec(span)
+            | Op::Stdout(span)
+            | Op::Assign(_, span)
+            | Op::AssignMut(_, span)
+            | Op::Rebind(_, span)
+            | Op::Repeat(_, _, span)
+            | Op::Until(_, _, _, span)
+            | Op::Group(_, span)
+            | Op::Call(_, span)
+            | Op::IfZero(_, _, span) => *span,
+        }
+    }
+}
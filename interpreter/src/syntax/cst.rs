@@ -0,0 +1,370 @@
+use std::fmt::{Display, Formatter};
+use std::iter::Peekable;
+use std::vec::IntoIter;
+
+use crate::lex::tokens::TokenName;
+use crate::lex::LexAnalyzer;
+use crate::span::Span;
+use crate::syntax::parser::MAX_NESTING_DEPTH;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum CstKind {
+    ProgramStart,
+    Statement,
+    Source,
+    /// `@name :=`, at the start of an operator definition.
+    Definition,
+    /// `BEGIN` or `END`, opening or closing a block.
+    Block,
+    /// `IMPORT "path" AS name`, without the `;`.
+    Import,
+    /// An operator, or `MUT` or `SET` followed by the variable they apply to.
+    UnaryOp,
+    RepeatedUnaryOp,
+    Group,
+    /// `?zero`, followed by a `Group` holding both branches, split by a `|`.
+    IfZero,
+    EndOfLine,
+    /// Input that doesn't fit the grammar. The parser reports an error instead.
+    Error,
+}
+
+/// A leaf of the concrete syntax tree: one lexeme, exactly as it was written.
+///
+/// Unlike `Token`, trivia (whitespace and comments) and input that failed to lex
+/// (`TokenName::Unknown`) are kept as leaves too.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CstToken {
+    pub name: TokenName,
+    pub text: String,
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum CstElement {
+    Node(CstNode),
+    Token(CstToken),
+}
+
+impl CstElement {
+    pub fn span(&self) -> Span {
+        match self {
+            CstElement::Node(node) => node.span,
+            CstElement::Token(token) => token.span,
+        }
+    }
+}
+
+/// A lossless concrete syntax tree, sitting underneath the AST in `syntax::ast`.
+///
+/// Every byte of the source belongs to exactly one `CstToken`, so `to_string()`
+/// reproduces the original input. Trivia between two statements is attached to the
+/// statement that follows it, and trivia after the last statement to the root.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CstNode {
+    pub kind: CstKind,
+    pub children: Vec<CstElement>,
+    /// Covers all of this node's children, including trivia.
+    pub span: Span,
+}
+
+impl Display for CstNode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.children.iter().try_for_each(|child| match child {
+            CstElement::Node(node) => write!(f, "{}", node),
+            CstElement::Token(token) => write!(f, "{}", token.text),
+        })
+    }
+}
+
+impl CstNode {
+    pub fn new(kind: CstKind) -> Self {
+        CstNode {
+            kind,
+            children: Vec::new(),
+            span: Span::default(),
+        }
+    }
+
+    /// Builds the concrete syntax tree for `input`. This never fails: anything that
+    /// doesn't fit the grammar ends up in a `CstKind::Error` node, including operators
+    /// nested more than `MAX_NESTING_DEPTH` deep.
+    pub fn parse(input: &str) -> CstNode {
+        let tokens: Vec<CstToken> = LexAnalyzer::new()
+            .tokens(input)
+            .map(|result| {
+                let (name, span) = match result {
+                    Ok(token) => (token.name, token.span),
+                    Err(error) => (TokenName::Unknown, error.span()),
+                };
+                CstToken {
+                    name,
+                    text: input[span.start..span.end].to_string(),
+                    span,
+                }
+            })
+            .collect();
+        CstBuilder {
+            tokens: tokens.into_iter().peekable(),
+        }
+        .program()
+    }
+
+    pub fn add_child(&mut self, child: CstElement) {
+        if self.children.is_empty() {
+            self.span = child.span();
+        } else {
+            self.span = self.span.to(&child.span());
+        }
+        self.children.push(child);
+    }
+
+    /// Finds the innermost `kind` node covering `span`, such as the span of an AST node
+    /// parsed from the same input.
+    pub fn node_for(&self, kind: CstKind, span: Span) -> Option<&CstNode> {
+        let contains = |outer: &Span| outer.start <= span.start && span.end <= outer.end;
+        let mut node = self;
+        let mut found = None;
+        loop {
+            if node.kind == kind {
+                found = Some(node);
+            }
+            let child = node.children.iter().find_map(|child| match child {
+                CstElement::Node(child) if contains(&child.span) => Some(child),
+                _ => None,
+            });
+            match child {
+                Some(child) => node = child,
+                None => return found,
+            }
+        }
+    }
+
+    /// All of the leaves under this node, in source order.
+    pub fn tokens(&self) -> Vec<&CstToken> {
+        let mut tokens = Vec::new();
+        let mut stack: Vec<&CstElement> = self.children.iter().rev().collect();
+        while let Some(element) = stack.pop() {
+            match element {
+                CstElement::Node(node) => stack.extend(node.children.iter().rev()),
+                CstElement::Token(token) => tokens.push(token),
+            }
+        }
+        tokens
+    }
+}
+
+struct CstBuilder {
+    tokens: Peekable<IntoIter<CstToken>>,
+}
+
+impl CstBuilder {
+    fn program(&mut self) -> CstNode {
+        let mut root = CstNode::new(CstKind::ProgramStart);
+        loop {
+            let leading_trivia = self.trivia();
+            if self.tokens.peek().is_none() {
+                leading_trivia.into_iter().for_each(|t| root.add_child(t));
+                return root;
+            }
+            let mut statement = CstNode::new(CstKind::Statement);
+            leading_trivia.into_iter().for_each(|t| statement.add_child(t));
+            self.statement(&mut statement);
+            root.add_child(CstElement::Node(statement));
+        }
+    }
+
+    fn statement(&mut self, statement: &mut CstNode) {
+        let source = self.tokens.next().expect("statement called at end of input");
+        let kind = match source.name {
+            TokenName::Letter
+            | TokenName::Integer
+            | TokenName::Float
+            | TokenName::UString
+            | TokenName::Variable => CstKind::Source,
+            TokenName::OperatorName => CstKind::Definition,
+            TokenName::Begin | TokenName::End => CstKind::Block,
+            TokenName::Import => CstKind::Import,
+            _ => CstKind::Error,
+        };
+        let mut head = CstNode::new(kind);
+        head.add_child(CstElement::Token(source));
+        // The rest of a definition or import's head is only part of it if it's there, and so
+        // is the trivia before each token.
+        let mut trivia = Vec::new();
+        let rest_of_head: &[TokenName] = match kind {
+            CstKind::Definition => &[TokenName::Define],
+            CstKind::Import => &[TokenName::UString, TokenName::As],
+            _ => &[],
+        };
+        for name in rest_of_head {
+            trivia.extend(self.trivia());
+            match self.tokens.next_if(|t| t.name == *name) {
+                Some(token) => {
+                    trivia.drain(..).for_each(|t| head.add_child(t));
+                    head.add_child(CstElement::Token(token));
+                }
+                None => break,
+            }
+        }
+        statement.add_child(CstElement::Node(head));
+        trivia.into_iter().for_each(|t| statement.add_child(t));
+        self.operators(statement);
+    }
+
+    /// Builds the rest of a statement, from after its source up to its `;`.
+    fn operators(&mut self, statement: &mut CstNode) {
+        loop {
+            self.trivia().into_iter().for_each(|t| statement.add_child(t));
+            let Some(token) = self.tokens.peek() else {
+                return;
+            };
+            if token.name == TokenName::Semicolon {
+                let end = self.tokens.next().unwrap();
+                statement.add_child(leaf_node(CstKind::EndOfLine, end));
+                return;
+            }
+            let child = match self.operator(0) {
+                Some(op) => op,
+                None => leaf_node(CstKind::Error, self.tokens.next().unwrap()),
+            };
+            statement.add_child(child);
+        }
+    }
+
+    /// Builds the operator starting at the next token, if one starts there, `depth` groups,
+    /// repeaters and conditionals deep. Like the parser, it won't go past
+    /// `MAX_NESTING_DEPTH`: a token that would nest any deeper is left as an error.
+    fn operator(&mut self, depth: usize) -> Option<CstElement> {
+        let name = self.tokens.peek()?.name;
+        let nests = matches!(
+            name,
+            TokenName::Repeater | TokenName::Until | TokenName::OpenParen | TokenName::IfZero
+        );
+        if nests && depth == MAX_NESTING_DEPTH {
+            return Some(leaf_node(CstKind::Error, self.tokens.next().unwrap()));
+        }
+        let op = match name {
+            TokenName::Repeater | TokenName::Until => CstElement::Node(self.repeated_op(depth)),
+            TokenName::OpenParen => CstElement::Node(self.group(false, depth)),
+            TokenName::IfZero => CstElement::Node(self.if_zero(depth)),
+            TokenName::Mut | TokenName::Set => CstElement::Node(self.keyword_variable()),
+            TokenName::Plus
+            | TokenName::Minus
+            | TokenName::Stdout
+            | TokenName::Variable
+            | TokenName::OperatorName => leaf_node(CstKind::UnaryOp, self.tokens.next().unwrap()),
+            _ => return None,
+        };
+        Some(op)
+    }
+
+    /// Builds `MUT` or `SET`, and the variable after it if there is one.
+    fn keyword_variable(&mut self) -> CstNode {
+        let mut op = CstNode::new(CstKind::UnaryOp);
+        let keyword = self.tokens.next().expect("keyword_variable called without a keyword");
+        op.add_child(CstElement::Token(keyword));
+        self.trivia().into_iter().for_each(|t| op.add_child(t));
+        if let Some(variable) = self.tokens.next_if(|t| t.name == TokenName::Variable) {
+            op.add_child(CstElement::Token(variable));
+        }
+        op
+    }
+
+    /// Builds `?zero` and its branches, if they follow it.
+    fn if_zero(&mut self, depth: usize) -> CstNode {
+        let mut if_zero = CstNode::new(CstKind::IfZero);
+        let token = self.tokens.next().expect("if_zero called without a ?zero");
+        if_zero.add_child(CstElement::Token(token));
+        self.trivia().into_iter().for_each(|t| if_zero.add_child(t));
+        if self.tokens.peek().is_some_and(|t| t.name == TokenName::OpenParen) {
+            if_zero.add_child(CstElement::Node(self.group(true, depth)));
+        }
+        if_zero
+    }
+
+    /// Builds a group, from its `(` up to its `)` or the end of the statement. `branches`
+    /// allows `|` tokens in it, as in `?zero`. The operators in it are one level deeper
+    /// than `depth`.
+    fn group(&mut self, branches: bool, depth: usize) -> CstNode {
+        let mut group = CstNode::new(CstKind::Group);
+        let open = self.tokens.next().expect("group called without a (");
+        group.add_child(CstElement::Token(open));
+        loop {
+            self.trivia().into_iter().for_each(|t| group.add_child(t));
+            let Some(token) = self.tokens.peek() else {
+                return group;
+            };
+            match token.name {
+                TokenName::CloseParen => {
+                    let close = self.tokens.next().unwrap();
+                    group.add_child(CstElement::Token(close));
+                    return group;
+                }
+                // Leave the `;` to end the statement.
+                TokenName::Semicolon => return group,
+                TokenName::Pipe if branches => {
+                    let pipe = self.tokens.next().unwrap();
+                    group.add_child(CstElement::Token(pipe));
+                }
+                _ => {
+                    let child = match self.operator(depth + 1) {
+                        Some(op) => op,
+                        None => leaf_node(CstKind::Error, self.tokens.next().unwrap()),
+                    };
+                    group.add_child(child);
+                }
+            }
+        }
+    }
+
+    /// Builds a (possibly nested) repeater, without recursing once per repeater. Each
+    /// repeater is one level deeper than the one before, starting at `depth`.
+    fn repeated_op(&mut self, depth: usize) -> CstNode {
+        let mut repeaters: Vec<CstNode> = Vec::new();
+        let is_repeater = |t: &CstToken| matches!(t.name, TokenName::Repeater | TokenName::Until);
+        while depth + repeaters.len() < MAX_NESTING_DEPTH {
+            let Some(token) = self.tokens.next_if(is_repeater) else {
+                break;
+            };
+            let mut repeater = CstNode::new(CstKind::RepeatedUnaryOp);
+            repeater.add_child(CstElement::Token(token));
+            self.trivia().into_iter().for_each(|t| repeater.add_child(t));
+            repeaters.push(repeater);
+        }
+        let op_depth = depth + repeaters.len();
+        let mut node = repeaters.pop().expect("repeated_op called without a repeater");
+        let op = match self.tokens.peek().map(|t| t.name) {
+            Some(TokenName::Plus | TokenName::Minus | TokenName::Stdout | TokenName::OperatorName) => {
+                Some(leaf_node(CstKind::UnaryOp, self.tokens.next().unwrap()))
+            }
+            // A repeater is only left here if it's too deeply nested, and becomes an error.
+            Some(
+                TokenName::OpenParen | TokenName::IfZero | TokenName::Repeater | TokenName::Until,
+            ) => self.operator(op_depth),
+            _ => None,
+        };
+        if let Some(op) = op {
+            node.add_child(op);
+        }
+        while let Some(mut parent) = repeaters.pop() {
+            parent.add_child(CstElement::Node(node));
+            node = parent;
+        }
+        node
+    }
+
+    fn trivia(&mut self) -> Vec<CstElement> {
+        let mut trivia = Vec::new();
+        while let Some(token) = self.tokens.next_if(|t| t.name.is_trivia()) {
+            trivia.push(CstElement::Token(token));
+        }
+        trivia
+    }
+}
+
+fn leaf_node(kind: CstKind, token: CstToken) -> CstElement {
+    let mut node = CstNode::new(kind);
+    node.add_child(CstElement::Token(token));
+    CstElement::Node(node)
+}
@@ -0,0 +1,181 @@
+use crate::interpret::UValue;
+use crate::span::Span;
+use crate::syntax::ast::{Op, Program, RepeatCount, Source, Statement, UntilTarget};
+
+/// Rewrites a program, taking each node and returning what replaces it.
+///
+/// Like `Visitor`, each method is called on one kind of node, and by default rebuilds it
+/// from its folded children by calling the `fold_` function of the same name. Override
+/// the methods for the nodes you want to change:
+///
+/// ```
+/// use u::syntax::ast::Op;
+/// use u::syntax::fold::{fold_op, Folder};
+///
+/// /// Swaps every `+` for a `-`, and the other way around.
+/// struct Invert;
+///
+/// impl Folder for Invert {
+///     fn fold_op(&mut self, op: Op) -> Op {
+///         match fold_op(self, op) {
+///             Op::Inc(span) => Op::Dec(span),
+///             Op::Dec(span) => Op::Inc(span),
+///             op => op,
+///         }
+///     }
+/// }
+/// ```
+///
+/// The result isn't checked; resolve it with a `SymbolTable` before running it.
+pub trait Folder {
+    fn fold_program(&mut self, program: Program) -> Program {
+        fold_program(self, program)
+    }
+
+    fn fold_statement(&mut self, statement: Statement) -> Statement {
+        fold_statement(self, statement)
+    }
+
+    fn fold_source(&mut self, source: Source) -> Source {
+        fold_source(self, source)
+    }
+
+    /// Called for each run of operators: those in a statement, a group or a branch of a
+    /// conditional. Override it to add or remove operators, rather than replace them.
+    fn fold_ops(&mut self, ops: Vec<Op>) -> Vec<Op> {
+        fold_ops(self, ops)
+    }
+
+    /// Called for every operator, including the ones inside repeaters, groups and
+    /// conditionals.
+    fn fold_op(&mut self, op: Op) -> Op {
+        fold_op(self, op)
+    }
+
+    fn fold_repeat_count(&mut self, count: RepeatCount) -> RepeatCount {
+        fold_repeat_count(self, count)
+    }
+
+    fn fold_until_target(&mut self, target: UntilTarget) -> UntilTarget {
+        fold_until_target(self, target)
+    }
+
+    /// Called for literals, in sources and loop targets.
+    fn fold_value(&mut self, value: UValue) -> UValue {
+        value
+    }
+
+    /// Called for every span in the program.
+    fn fold_span(&mut self, span: Span) -> Span {
+        span
+    }
+}
+
+pub fn fold_program<F: Folder + ?Sized>(folder: &mut F, program: Program) -> Program {
+    Program {
+        statements: program
+            .statements
+            .into_iter()
+            .map(|statement| folder.fold_statement(statement))
+            .collect(),
+    }
+}
+
+pub fn fold_statement<F: Folder + ?Sized>(folder: &mut F, statement: Statement) -> Statement {
+    match statement {
+        Statement::Chain { source, ops, span } => Statement::Chain {
+            span: folder.fold_span(span),
+            source: folder.fold_source(source),
+            ops: folder.fold_ops(ops),
+        },
+        Statement::Define {
+            name,
+            name_span,
+            ops,
+            span,
+        } => Statement::Define {
+            span: folder.fold_span(span),
+            name,
+            name_span: folder.fold_span(name_span),
+            ops: folder.fold_ops(ops),
+        },
+        Statement::Begin { span } => Statement::Begin {
+            span: folder.fold_span(span),
+        },
+        Statement::End { span } => Statement::End {
+            span: folder.fold_span(span),
+        },
+        Statement::Import {
+            path,
+            path_span,
+            alias,
+            alias_span,
+            span,
+        } => Statement::Import {
+            span: folder.fold_span(span),
+            path,
+            path_span: folder.fold_span(path_span),
+            alias,
+            alias_span: folder.fold_span(alias_span),
+        },
+    }
+}
+
+pub fn fold_source<F: Folder + ?Sized>(folder: &mut F, source: Source) -> Source {
+    match source {
+        Source::Literal(value, span) => {
+            Source::Literal(folder.fold_value(value), folder.fold_span(span))
+        }
+        Source::Var(name, span) => Source::Var(name, folder.fold_span(span)),
+    }
+}
+
+pub fn fold_ops<F: Folder + ?Sized>(folder: &mut F, ops: Vec<Op>) -> Vec<Op> {
+    ops.into_iter().map(|op| folder.fold_op(op)).collect()
+}
+
+pub fn fold_op<F: Folder + ?Sized>(folder: &mut F, op: Op) -> Op {
+    match op {
+        Op::Inc(span) => Op::Inc(folder.fold_span(span)),
+        Op::Dec(span) => Op::Dec(folder.fold_span(span)),
+        Op::Stdout(span) => Op::Stdout(folder.fold_span(span)),
+        Op::Assign(name, span) => Op::Assign(name, folder.fold_span(span)),
+        Op::AssignMut(name, span) => Op::AssignMut(name, folder.fold_span(span)),
+        Op::Rebind(name, span) => Op::Rebind(name, folder.fold_span(span)),
+        Op::Call(name, span) => Op::Call(name, folder.fold_span(span)),
+        Op::Repeat(count, repeated, span) => {
+            let span = folder.fold_span(span);
+            let count = folder.fold_repeat_count(count);
+            Op::Repeat(count, Box::new(folder.fold_op(*repeated)), span)
+        }
+        Op::Until(target, limit, repeated, span) => {
+            let span = folder.fold_span(span);
+            let target = folder.fold_until_target(target);
+            Op::Until(target, limit, Box::new(folder.fold_op(*repeated)), span)
+        }
+        Op::Group(ops, span) => {
+            let span = folder.fold_span(span);
+            Op::Group(folder.fold_ops(ops), span)
+        }
+        Op::IfZero(if_zero, otherwise, span) => {
+            let span = folder.fold_span(span);
+            let if_zero = folder.fold_ops(if_zero);
+            Op::IfZero(if_zero, folder.fold_ops(otherwise), span)
+        }
+    }
+}
+
+pub fn fold_repeat_count<F: Folder + ?Sized>(folder: &mut F, count: RepeatCount) -> RepeatCount {
+    match count {
+        RepeatCount::Literal(count) => RepeatCount::Literal(count),
+        RepeatCount::Var(name, span) => RepeatCount::Var(name, folder.fold_span(span)),
+    }
+}
+
+pub fn fold_until_target<F: Folder + ?Sized>(folder: &mut F, target: UntilTarget) -> UntilTarget {
+    match target {
+        UntilTarget::Literal(value) => UntilTarget::Literal(folder.fold_value(value)),
+        UntilTarget::Var(name, span) => UntilTarget::Var(name, folder.fold_span(span)),
+        UntilTarget::FixedPoint => UntilTarget::FixedPoint,
+    }
+}
@@ -1,18 +1,37 @@
-use std::collections::HashSet;
 use std::error::Error;
 use std::fmt::{Display, Formatter};
 use std::iter::Peekable;
 use std::slice::Iter;
 
-use crate::lex::tokens::{Token, TokenName};
-use crate::syntax::tree::{SyntaxTree, SyntaxTreeKind};
+use crate::interpret::{UType, UValue};
+use crate::lex::tokens::{parse_float_literal, parse_integer_literal, Token, TokenName};
+use crate::span::Span;
+use crate::syntax::ast::{Op, Program, RepeatCount, Source, Statement, UntilTarget};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum SyntaxError {
-    UnexpectedToken { unexpected: Token, message: String },
-    UndeclaredVariable { var_name: String },
-    AssignedVariableTwice { var_name: String },
-    LineIncomplete,
+    UnexpectedToken { unexpected: Token, expected: Vec<TokenName> },
+    InvalidLiteral { value: String, message: String, span: Span },
+    UndeclaredVariable { var_name: String, span: Span },
+    AssignedVariableTwice { var_name: String, span: Span, first_assigned: Span },
+    AssignedInRepeat { var_name: String, span: Span },
+    AssignedInDefinition { var_name: String, span: Span },
+    AssignedInConditional { var_name: String, span: Span },
+    UndefinedOperator { op_name: String, span: Span },
+    UnreachableTarget { target_type: UType, utype: UType, span: Span },
+    RebindImmutable { var_name: String, span: Span, first_assigned: Span },
+    RebindTypeMismatch { var_name: String, declared: UType, utype: UType, span: Span },
+    UnmatchedEnd { span: Span },
+    UnclosedBlock { span: Span },
+    ImportFailed { path: String, reason: String, span: Span },
+    ImportedTwice { alias: String, span: Span, first_imported: Span },
+    /// A `.` in the name of a variable or operator being defined, which only imported
+    /// ones can have.
+    QualifiedDefinition { name: String, span: Span },
+    DefinedOperatorTwice { op_name: String, span: Span, first_defined: Span },
+    LineIncomplete { span: Span, expected: Vec<TokenName> },
+    /// A group, branch or repeater inside more than `MAX_NESTING_DEPTH` others.
+    NestedTooDeeply { span: Span },
 }
 
 impl Display for SyntaxError {
@@ -20,211 +39,921 @@ impl Display for SyntaxError {
         match self {
             SyntaxError::UnexpectedToken {
                 unexpected,
+                expected,
+            } => {
+                write!(
+                    f,
+                    "{}: Unexpected token: {:?} '{}', expected {}",
+                    unexpected.span,
+                    unexpected.name,
+                    unexpected.value,
+                    describe_all(expected)
+                )
+            }
+            SyntaxError::InvalidLiteral {
+                value,
                 message,
+                span,
+            } => {
+                write!(f, "{}: {}: {}", span, message, value)
+            }
+            SyntaxError::LineIncomplete { span, expected } => {
+                write!(
+                    f,
+                    "{}: Expected more tokens before end of line, expected {}",
+                    span,
+                    describe_all(expected)
+                )
+            }
+            SyntaxError::UndeclaredVariable { var_name, span } => {
+                write!(f, "{}: Variable not declared: {}", span, var_name)
+            }
+            SyntaxError::AssignedVariableTwice {
+                var_name,
+                span,
+                first_assigned,
+            } => {
+                write!(
+                    f,
+                    "{}: Cannot assign to this variable twice: {} (first assigned at {})",
+                    span, var_name, first_assigned
+                )
+            }
+            SyntaxError::AssignedInRepeat { var_name, span } => {
+                write!(
+                    f,
+                    "{}: Cannot assign to a variable inside a repeated group: {}",
+                    span, var_name
+                )
+            }
+            SyntaxError::AssignedInDefinition { var_name, span } => {
+                write!(
+                    f,
+                    "{}: Cannot assign to a variable inside an operator definition: {}",
+                    span, var_name
+                )
+            }
+            SyntaxError::AssignedInConditional { var_name, span } => {
+                write!(
+                    f,
+                    "{}: Cannot assign to a variable inside a ?zero branch: {}",
+                    span, var_name
+                )
+            }
+            SyntaxError::UnreachableTarget {
+                target_type,
+                utype,
+                span,
+            } => {
+                write!(
+                    f,
+                    "{}: Loop target has type {}, so a value of type {} can never reach it",
+                    span, target_type, utype
+                )
+            }
+            SyntaxError::RebindImmutable {
+                var_name,
+                span,
+                first_assigned,
+            } => {
+                write!(
+                    f,
+                    "{}: Cannot SET a variable that wasn't declared with MUT: {} (first assigned at {})",
+                    span, var_name, first_assigned
+                )
+            }
+            SyntaxError::RebindTypeMismatch {
+                var_name,
+                declared,
+                utype,
+                span,
+            } => {
+                write!(
+                    f,
+                    "{}: Cannot SET {} to a value of type {}, it was declared with type {}",
+                    span, var_name, utype, declared
+                )
+            }
+            SyntaxError::UnmatchedEnd { span } => {
+                write!(f, "{}: END without a matching BEGIN", span)
+            }
+            SyntaxError::UnclosedBlock { span } => {
+                write!(f, "{}: BEGIN without a matching END", span)
+            }
+            SyntaxError::ImportFailed { path, reason, span } => {
+                write!(f, "{}: Cannot import \"{}\": {}", span, path, reason)
+            }
+            SyntaxError::ImportedTwice {
+                alias,
+                span,
+                first_imported,
             } => {
-                write!(f, "Unexpected token: {:?}, {}", unexpected, message)
+                write!(
+                    f,
+                    "{}: Cannot import twice as the same name: {} (first imported at {})",
+                    span, alias, first_imported
+                )
             }
-            SyntaxError::LineIncomplete => {
-                write!(f, "Expected more tokens before end of line")
+            SyntaxError::QualifiedDefinition { name, span } => {
+                write!(
+                    f,
+                    "{}: Only imported variables and operators can have a . in their name: {}",
+                    span, name
+                )
             }
-            SyntaxError::UndeclaredVariable { var_name } => {
-                write!(f, "Variable not declared: {}", var_name)
+            SyntaxError::UndefinedOperator { op_name, span } => {
+                write!(f, "{}: Operator not defined: {}", span, op_name)
             }
-            SyntaxError::AssignedVariableTwice { var_name } => {
-                write!(f, "Cannot assign to this variable twice: {}", var_name)
+            SyntaxError::DefinedOperatorTwice {
+                op_name,
+                span,
+                first_defined,
+            } => {
+                write!(
+                    f,
+                    "{}: Cannot define this operator twice: {} (first defined at {})",
+                    span, op_name, first_defined
+                )
+            }
+            SyntaxError::NestedTooDeeply { span } => {
+                write!(
+                    f,
+                    "{}: Operators nested too deeply (more than {} levels)",
+                    span, MAX_NESTING_DEPTH
+                )
             }
         }
     }
 }
 impl Error for SyntaxError {}
 
+impl SyntaxError {
+    /// The location of the offending input.
+    pub fn span(&self) -> Span {
+        match self {
+            SyntaxError::UnexpectedToken { unexpected, .. } => unexpected.span,
+            SyntaxError::InvalidLiteral { span, .. }
+            | SyntaxError::UndeclaredVariable { span, .. }
+            | SyntaxError::AssignedVariableTwice { span, .. }
+            | SyntaxError::AssignedInRepeat { span, .. }
+            | SyntaxError::AssignedInDefinition { span, .. }
+            | SyntaxError::AssignedInConditional { span, .. }
+            | SyntaxError::UndefinedOperator { span, .. }
+            | SyntaxError::UnreachableTarget { span, .. }
+            | SyntaxError::RebindImmutable { span, .. }
+            | SyntaxError::RebindTypeMismatch { span, .. }
+            | SyntaxError::UnmatchedEnd { span }
+            | SyntaxError::UnclosedBlock { span }
+            | SyntaxError::ImportFailed { span, .. }
+            | SyntaxError::ImportedTwice { span, .. }
+            | SyntaxError::QualifiedDefinition { span, .. }
+            | SyntaxError::DefinedOperatorTwice { span, .. }
+            | SyntaxError::LineIncomplete { span, .. }
+            | SyntaxError::NestedTooDeeply { span } => *span,
+        }
+    }
+}
+
+/// Lists tokens for an error message, like "+, -, STDOUT or ;".
+fn describe_all(names: &[TokenName]) -> String {
+    let descriptions: Vec<&str> = names.iter().map(|name| name.describe()).collect();
+    match descriptions.split_last() {
+        None => String::from("nothing"),
+        Some((last, [])) => last.to_string(),
+        Some((last, rest)) => format!("{} or {}", rest.join(", "), last),
+    }
+}
+
+/// The tokens a statement can start with.
+const SOURCES: &[TokenName] = &[
+    TokenName::Letter,
+    TokenName::Integer,
+    TokenName::Float,
+    TokenName::UString,
+    TokenName::Variable,
+    TokenName::OperatorName,
+    TokenName::Begin,
+    TokenName::End,
+    TokenName::Import,
+];
+
+/// The tokens that can follow a statement's source or any of its operators.
+const OPERATORS: &[TokenName] = &[
+    TokenName::Plus,
+    TokenName::Minus,
+    TokenName::Stdout,
+    TokenName::Repeater,
+    TokenName::Until,
+    TokenName::OpenParen,
+    TokenName::IfZero,
+    TokenName::Variable,
+    TokenName::Mut,
+    TokenName::Set,
+    TokenName::OperatorName,
+    TokenName::Semicolon,
+];
+
+/// The tokens that can follow a `{n}` or `{until x}` repeater.
+const REPEATABLE: &[TokenName] = &[
+    TokenName::Plus,
+    TokenName::Minus,
+    TokenName::Stdout,
+    TokenName::Repeater,
+    TokenName::Until,
+    TokenName::OpenParen,
+    TokenName::IfZero,
+    TokenName::OperatorName,
+];
+
+/// The tokens that can follow a `(` or any operator inside a group.
+const GROUPED: &[TokenName] = &[
+    TokenName::Plus,
+    TokenName::Minus,
+    TokenName::Stdout,
+    TokenName::Repeater,
+    TokenName::Until,
+    TokenName::OpenParen,
+    TokenName::IfZero,
+    TokenName::Variable,
+    TokenName::Mut,
+    TokenName::Set,
+    TokenName::OperatorName,
+    TokenName::CloseParen,
+];
+
+/// The tokens that can follow `?zero(` or any operator in its first branch.
+const FIRST_BRANCH: &[TokenName] = &[
+    TokenName::Plus,
+    TokenName::Minus,
+    TokenName::Stdout,
+    TokenName::Repeater,
+    TokenName::Until,
+    TokenName::OpenParen,
+    TokenName::IfZero,
+    TokenName::Variable,
+    TokenName::Mut,
+    TokenName::Set,
+    TokenName::OperatorName,
+    TokenName::Pipe,
+];
+
+/// Parsers only check the structure of the program; whether variables and named
+/// operators are defined before they're used is checked afterwards, by `SymbolTable::resolve`.
 pub trait SyntaxParser {
+    type Output;
+
     fn parse(
         &mut self,
-        variables_in_scope: &mut HashSet<String>,
         tokens: &mut Peekable<Iter<'_, Token>>,
-    ) -> Result<SyntaxTree, Vec<SyntaxError>>;
+    ) -> Result<Self::Output, Vec<SyntaxError>>;
+}
+
+fn skip_trivia(tokens: &mut Peekable<Iter<'_, Token>>) {
+    while tokens.peek().is_some_and(|it| it.name.is_trivia()) {
+        tokens.next();
+    }
+}
+
+/// Like `tokens.peek()`, but steps over any whitespace and comments first.
+fn peek_significant<'a>(tokens: &mut Peekable<Iter<'a, Token>>) -> Option<&'a Token> {
+    skip_trivia(tokens);
+    tokens.peek().copied()
+}
+
+/// Panic-mode error recovery: throws away the rest of a malformed statement, up to
+/// and including its `;`, so that the next statement is parsed from a clean slate.
+fn skip_statement(tokens: &mut Peekable<Iter<'_, Token>>) {
+    for token in tokens.by_ref() {
+        if token.name == TokenName::Semicolon {
+            break;
+        }
+    }
+}
+
+/// Converts a literal token into the value it stands for.
+fn literal(token: &Token) -> Result<UValue, SyntaxError> {
+    let invalid = |message: String| SyntaxError::InvalidLiteral {
+        value: token.value.clone(),
+        message,
+        span: token.span,
+    };
+    match token.name {
+        TokenName::Integer => parse_integer_literal(&token.value)
+            .map(UValue::Integer)
+            .map_err(invalid),
+        TokenName::Float => parse_float_literal(&token.value)
+            .map(UValue::Float)
+            .map_err(invalid),
+        TokenName::Letter => {
+            let mut chars = token.value.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Ok(UValue::Letter(c)),
+                _ => Err(invalid(String::from("Letters hold a single character"))),
+            }
+        }
+        TokenName::UString => Ok(UValue::UString(token.value.clone())),
+        _ => Err(SyntaxError::UnexpectedToken {
+            unexpected: token.clone(),
+            expected: SOURCES.to_vec(),
+        }),
+    }
+}
+
+/// How deeply groups, `?zero` branches and repeaters may be nested in each other. Every
+/// later pass walks the syntax tree recursively, so this keeps them from running out of
+/// stack, however the program was written.
+pub const MAX_NESTING_DEPTH: usize = 256;
+
+/// Parses the operator starting at the next token, if there is one there. `depth` is how
+/// many groups, branches and repeaters it's inside.
+///
+/// Returns `None`, without consuming anything, if the next token can't start an operator.
+fn operator(
+    tokens: &mut Peekable<Iter<'_, Token>>,
+    depth: usize,
+) -> Option<Result<Op, Vec<SyntaxError>>> {
+    let token = peek_significant(tokens)?;
+    let nests = matches!(
+        token.name,
+        TokenName::Repeater | TokenName::Until | TokenName::OpenParen | TokenName::IfZero
+    );
+    if nests && depth == MAX_NESTING_DEPTH {
+        return Some(Err(vec![SyntaxError::NestedTooDeeply { span: token.span }]));
+    }
+    let op = match token.name {
+        TokenName::Plus => Ok(Op::Inc(token.span)),
+        TokenName::Minus => Ok(Op::Dec(token.span)),
+        TokenName::Stdout => Ok(Op::Stdout(token.span)),
+        TokenName::Variable => Ok(Op::Assign(token.value.clone(), token.span)),
+        TokenName::OperatorName => Ok(Op::Call(token.value.clone(), token.span)),
+        TokenName::Repeater | TokenName::Until => {
+            tokens.next();
+            return Some(RepeaterParser { token: token.clone(), depth }.parse(tokens));
+        }
+        TokenName::OpenParen => {
+            tokens.next();
+            return Some(GroupParser { token: token.clone(), depth }.parse(tokens));
+        }
+        TokenName::IfZero => {
+            tokens.next();
+            return Some(IfZeroParser { token: token.clone(), depth }.parse(tokens));
+        }
+        TokenName::Mut | TokenName::Set => {
+            tokens.next();
+            return Some(keyword_variable(token, tokens));
+        }
+        _ => return None,
+    };
+    tokens.next();
+    Some(op)
+}
+
+/// Parses the variable after `MUT` or `SET`, which has already been consumed.
+///
+/// Leaves an unexpected token in place, for the caller to recover from.
+fn keyword_variable(
+    keyword: &Token,
+    tokens: &mut Peekable<Iter<'_, Token>>,
+) -> Result<Op, Vec<SyntaxError>> {
+    match peek_significant(tokens) {
+        Some(token) if token.name == TokenName::Variable => {
+            tokens.next();
+            let name = token.value.clone();
+            let span = keyword.span.to(&token.span);
+            match keyword.name {
+                TokenName::Mut => Ok(Op::AssignMut(name, span)),
+                _ => Ok(Op::Rebind(name, span)),
+            }
+        }
+        Some(token) => Err(vec![SyntaxError::UnexpectedToken {
+            unexpected: token.clone(),
+            expected: vec![TokenName::Variable],
+        }]),
+        None => Err(vec![SyntaxError::LineIncomplete {
+            span: keyword.span,
+            expected: vec![TokenName::Variable],
+        }]),
+    }
+}
+
+/// Parses the rest of `IMPORT "path" AS name;`, after the `IMPORT`.
+fn import(
+    keyword: &Token,
+    tokens: &mut Peekable<Iter<'_, Token>>,
+) -> Result<Statement, Vec<SyntaxError>> {
+    let mut span = keyword.span;
+    let mut expect = |name: TokenName| match peek_significant(tokens) {
+        Some(token) if token.name == name => {
+            tokens.next();
+            span = span.to(&token.span);
+            Ok(token)
+        }
+        Some(token) => Err(vec![SyntaxError::UnexpectedToken {
+            unexpected: token.clone(),
+            expected: vec![name],
+        }]),
+        None => Err(vec![SyntaxError::LineIncomplete {
+            span,
+            expected: vec![name],
+        }]),
+    };
+    let path = expect(TokenName::UString)?;
+    let alias = expect(TokenName::As)?;
+    expect(TokenName::Semicolon)?;
+    Ok(Statement::Import {
+        path: path.value.clone(),
+        path_span: path.span,
+        alias: alias.value.clone(),
+        alias_span: alias.span,
+        span,
+    })
 }
 
 #[derive(Debug, PartialEq)]
 pub struct ProgramParser;
 
 impl SyntaxParser for ProgramParser {
+    type Output = Program;
+
     fn parse(
         &mut self,
-        variables_in_scope: &mut HashSet<String>,
         tokens: &mut Peekable<Iter<'_, Token>>,
-    ) -> Result<SyntaxTree, Vec<SyntaxError>> {
-        let mut tree = SyntaxTree::root();
+    ) -> Result<Program, Vec<SyntaxError>> {
+        let (program, syntax_errors) = self.parse_all(tokens);
+        if syntax_errors.is_empty() {
+            Ok(program)
+        } else {
+            Err(syntax_errors)
+        }
+    }
+}
+
+impl Default for ProgramParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProgramParser {
+    pub fn new() -> Self {
+        ProgramParser {}
+    }
+
+    /// Like `parse`, but also returns the statements that did parse when others didn't.
+    pub fn parse_all(
+        &mut self,
+        tokens: &mut Peekable<Iter<'_, Token>>,
+    ) -> (Program, Vec<SyntaxError>) {
+        let mut program = Program::default();
         let mut syntax_errors: Vec<SyntaxError> = Vec::new();
 
         loop {
-            // kinda hacky, skip over whitespace
-            while let Some(TokenName::Whitespace) = tokens.peek().and_then(|it| Some(it.name)) {
-                tokens.next();
-            }
-            if let None = tokens.peek() {
+            skip_trivia(tokens);
+            if tokens.peek().is_none() {
                 break;
             }
             let mut sp = StatementParser {};
-            let result = sp.parse(variables_in_scope, tokens);
+            let result = sp.parse(tokens);
             match result {
-                Ok(subtree) => tree.add_child(subtree),
+                Ok(statement) => program.statements.push(statement),
                 Err(errors) => {
                     errors.iter().for_each(|e| syntax_errors.push(e.clone()));
                 }
             }
         }
-
-        if syntax_errors.is_empty() {
-            Ok(tree)
-        } else {
-            Err(syntax_errors)
-        }
-    }
-}
-
-impl ProgramParser {
-    pub fn new() -> Self {
-        ProgramParser {}
+        (program, syntax_errors)
     }
 }
 
+/// Parses one statement, through its `;`.
+///
+/// After an error that leaves the statement's structure unclear, such as an unexpected
+/// token, the rest of the statement is skipped rather than reporting every token in it.
 #[derive(Debug, PartialEq)]
 pub struct StatementParser;
 
 impl SyntaxParser for StatementParser {
+    type Output = Statement;
+
     fn parse(
         &mut self,
-        variables_in_scope: &mut HashSet<String>,
         tokens: &mut Peekable<Iter<'_, Token>>,
-    ) -> Result<SyntaxTree, Vec<SyntaxError>> {
-        let mut statement = SyntaxTree::new(SyntaxTreeKind::Statement, None);
+    ) -> Result<Statement, Vec<SyntaxError>> {
+        let mut ops: Vec<Op> = Vec::new();
         let mut errors: Vec<SyntaxError> = Vec::new();
 
-        let source_token = tokens
-            .next()
+        let first_token = peek_significant(tokens)
             .expect("Internal error: SyntaxParser.parse called with an empty token iterator");
-        match source_token.name {
+        let mut statement_span = first_token.span;
+        let head = match first_token.name {
             TokenName::Letter | TokenName::Integer | TokenName::Float | TokenName::UString => {
-                let source = SyntaxTree::new(SyntaxTreeKind::Source, Some(source_token.clone()));
-                statement.add_child(source);
+                match literal(first_token) {
+                    Ok(value) => Some(StatementHead::Source(Source::Literal(value, first_token.span))),
+                    Err(error) => {
+                        errors.push(error);
+                        None
+                    }
+                }
             }
-            TokenName::Variable => {
-                if variables_in_scope.contains(&source_token.value) {
-                    let source = SyntaxTree::new(SyntaxTreeKind::Source, Some(source_token.clone()));
-                    statement.add_child(source);
-                } else {
-                    errors.push(
-                        SyntaxError::UndeclaredVariable { var_name: source_token.value.clone() }
-                    )
+            TokenName::Variable => Some(StatementHead::Source(Source::Var(
+                first_token.value.clone(),
+                first_token.span,
+            ))),
+            TokenName::Begin | TokenName::End => {
+                tokens.next();
+                match peek_significant(tokens) {
+                    Some(token) if token.name == TokenName::Semicolon => {
+                        tokens.next();
+                        let span = statement_span.to(&token.span);
+                        return match first_token.name {
+                            TokenName::Begin => Ok(Statement::Begin { span }),
+                            _ => Ok(Statement::End { span }),
+                        };
+                    }
+                    Some(token) => {
+                        errors.push(SyntaxError::UnexpectedToken {
+                            unexpected: token.clone(),
+                            expected: vec![TokenName::Semicolon],
+                        });
+                        skip_statement(tokens);
+                        return Err(errors);
+                    }
+                    None => {
+                        errors.push(SyntaxError::LineIncomplete {
+                            span: statement_span,
+                            expected: vec![TokenName::Semicolon],
+                        });
+                        return Err(errors);
+                    }
                 }
             }
-            _ => {
-                errors.push(
-                    SyntaxError::UnexpectedToken {
-                        unexpected: source_token.clone(),
-                        message: String::from("StatementParser: expected statement to start with Letter/Integer/Float/UString"),
+            TokenName::Import => {
+                tokens.next();
+                return import(first_token, tokens).inspect_err(|_| skip_statement(tokens));
+            }
+            TokenName::OperatorName => {
+                // Step over the name, leaving its `:=` to be consumed below.
+                tokens.next();
+                match peek_significant(tokens) {
+                    Some(token) if token.name == TokenName::Define => {
+                        statement_span = statement_span.to(&token.span);
+                        Some(StatementHead::Define(first_token.value.clone(), first_token.span))
                     }
-                )
+                    Some(token) => {
+                        errors.push(SyntaxError::UnexpectedToken {
+                            unexpected: token.clone(),
+                            expected: vec![TokenName::Define],
+                        });
+                        skip_statement(tokens);
+                        return Err(errors);
+                    }
+                    None => {
+                        errors.push(SyntaxError::LineIncomplete {
+                            span: statement_span,
+                            expected: vec![TokenName::Define],
+                        });
+                        return Err(errors);
+                    }
+                }
             }
-        }
+            _ => {
+                errors.push(SyntaxError::UnexpectedToken {
+                    unexpected: first_token.clone(),
+                    expected: SOURCES.to_vec(),
+                });
+                skip_statement(tokens);
+                return Err(errors);
+            }
+        };
+        tokens.next();
 
         let mut line_completed = false;
-        while let Some(token) = tokens.next() {
-            match token.name {
-                TokenName::Plus | TokenName::Minus | TokenName::Stdout => {
-                    let op = SyntaxTree::new(SyntaxTreeKind::UnaryOp, Some(token.clone()));
-                    statement.add_child(op);
+        while let Some(token) = peek_significant(tokens) {
+            if token.name == TokenName::Semicolon {
+                tokens.next();
+                statement_span = statement_span.to(&token.span);
+                line_completed = true;
+                break;
+            }
+            match operator(tokens, 0) {
+                Some(Ok(op)) => {
+                    statement_span = statement_span.to(&op.span());
+                    ops.push(op);
                 }
-                TokenName::Variable => {
-                    if variables_in_scope.contains(&token.value) {
-                        errors.push(
-                            SyntaxError::AssignedVariableTwice { var_name: token.value.to_string() }
-                        )
-                    } else {
-                        variables_in_scope.insert(token.value.to_string());
-                        let op = SyntaxTree::new(SyntaxTreeKind::UnaryOp, Some(token.clone()));
-                        statement.add_child(op);
-                    }
+                Some(Err(op_errors)) => {
+                    errors.extend(op_errors);
+                    skip_statement(tokens);
+                    return Err(errors);
                 }
-                TokenName::Repeater => match RepeaterParser::new(token.clone()).parse(variables_in_scope, tokens) {
-                    Err(repeater_errors) => {
-                        repeater_errors.iter().for_each(|e| errors.push(e.clone()));
-                    }
-                    Ok(subtree) => {
-                        statement.add_child(subtree);
-                    }
-                },
-                TokenName::Semicolon => {
-                    let end = SyntaxTree::new(SyntaxTreeKind::EndOfLine, None);
-                    statement.add_child(end);
-                    line_completed = true;
-                    break;
+                None => {
+                    errors.push(SyntaxError::UnexpectedToken {
+                        unexpected: token.clone(),
+                        expected: OPERATORS.to_vec(),
+                    });
+                    skip_statement(tokens);
+                    return Err(errors);
                 }
-                _ => errors.push(SyntaxError::UnexpectedToken {
-                    unexpected: token.clone(),
-                    message: String::from("StatementParser: expected UnaryOperator or Semicolon"),
-                }),
             }
         }
         if !line_completed {
-            errors.push(SyntaxError::LineIncomplete);
+            errors.push(SyntaxError::LineIncomplete {
+                span: statement_span,
+                expected: OPERATORS.to_vec(),
+            });
         }
-        if errors.is_empty() {
-            Ok(statement)
-        } else {
-            Err(errors)
+        match head {
+            Some(StatementHead::Source(source)) if errors.is_empty() => Ok(Statement::Chain {
+                source,
+                ops,
+                span: statement_span,
+            }),
+            Some(StatementHead::Define(name, name_span)) if errors.is_empty() => {
+                Ok(Statement::Define {
+                    name,
+                    name_span,
+                    ops,
+                    span: statement_span,
+                })
+            }
+            _ => Err(errors),
         }
     }
 }
 
+/// What comes before a statement's operators.
+enum StatementHead {
+    Source(Source),
+    /// `@name :=`, with the span of the name.
+    Define(String, Span),
+}
+
+/// How many times `{until x}` may apply its operator, unless it says otherwise with `max`.
+pub const DEFAULT_MAX_ITERATIONS: u32 = 10_000;
+
+/// Parses the operator (or group) after a `{n}` or `{until x}` repeater, which has already
+/// been consumed.
+///
+/// Leaves an unexpected token in place, for the caller to recover from.
 #[derive(Debug, PartialEq)]
 pub struct RepeaterParser {
     token: Token,
+    /// How many groups, branches and repeaters the repeater is inside.
+    depth: usize,
+}
+
+/// What a repeater's braces hold.
+enum Repetition {
+    Count(RepeatCount),
+    Until(UntilTarget, u32),
 }
 
 impl RepeaterParser {
     pub fn new(token: Token) -> Self {
-        return RepeaterParser { token };
+        RepeaterParser { token, depth: 0 }
+    }
+
+    /// The span of the part of the token's value starting at byte `offset`, and `length` long.
+    fn span_within(&self, offset: usize, length: usize) -> Span {
+        // Skip the `{`, which isn't part of the value. Columns count characters, not bytes.
+        Span {
+            start: self.token.span.start + 1 + offset,
+            end: self.token.span.start + 1 + offset + length,
+            line: self.token.span.line,
+            column: self.token.span.column + 1 + self.token.value[..offset].chars().count(),
+        }
+    }
+
+    fn count(&self) -> Result<RepeatCount, SyntaxError> {
+        let max = u32::MAX as i64;
+        match self.token.value.parse::<i64>() {
+            Ok(count) if (-max..=max).contains(&count) => Ok(RepeatCount::Literal(count)),
+            _ if self.token.value.starts_with('$') => Ok(RepeatCount::Var(
+                self.token.value.clone(),
+                self.span_within(0, self.token.value.len()),
+            )),
+            _ => Err(SyntaxError::InvalidLiteral {
+                value: self.token.value.clone(),
+                message: format!("Repeat count out of range, expected between -{} and {}", max, max),
+                span: self.token.span,
+            }),
+        }
+    }
+
+    /// Picks apart `until <target> [max <n>]`, which the lexer has already checked the shape of.
+    fn until(&self) -> Result<(UntilTarget, u32), SyntaxError> {
+        let value = &self.token.value;
+        let invalid = |value: &str, message: String| SyntaxError::InvalidLiteral {
+            value: value.to_string(),
+            message,
+            span: self.token.span,
+        };
+        let words: Vec<&str> = value.split_whitespace().collect();
+        let target = match words[1] {
+            "fixed" => UntilTarget::FixedPoint,
+            var if var.starts_with('$') => {
+                let offset = value.find(var).unwrap_or_default();
+                UntilTarget::Var(var.to_string(), self.span_within(offset, var.len()))
+            }
+            "inf" | "-inf" | "nan" => UntilTarget::Literal(UValue::Float(
+                parse_float_literal(words[1]).map_err(|message| invalid(words[1], message))?,
+            )),
+            literal if literal.starts_with(|c: char| c == '-' || c.is_ascii_digit()) => {
+                let hex = literal.trim_start_matches('-').starts_with("0x");
+                let value = if literal.contains('.') || (!hex && literal.contains(['e', 'E'])) {
+                    parse_float_literal(literal).map(UValue::Float)
+                } else {
+                    parse_integer_literal(literal).map(UValue::Integer)
+                };
+                UntilTarget::Literal(value.map_err(|message| invalid(literal, message))?)
+            }
+            other => {
+                let message = String::from("Malformed loop target, expected $var, a number or fixed");
+                return Err(invalid(other, message));
+            }
+        };
+        let max_iterations = match words.get(3) {
+            Some(max) => max.parse::<u32>().map_err(|_| {
+                invalid(max, format!("Iteration limit out of range, expected at most {}", u32::MAX))
+            })?,
+            None => DEFAULT_MAX_ITERATIONS,
+        };
+        Ok((target, max_iterations))
     }
 }
 
 impl SyntaxParser for RepeaterParser {
+    type Output = Op;
+
     fn parse(
         &mut self,
-        variables_in_scope: &mut HashSet<String>,
         tokens: &mut Peekable<Iter<'_, Token>>,
-    ) -> Result<SyntaxTree, Vec<SyntaxError>> {
-        let mut subtree =
-            SyntaxTree::new(SyntaxTreeKind::RepeatedUnaryOp, Some(self.token.clone()));
+    ) -> Result<Op, Vec<SyntaxError>> {
         let mut errors: Vec<SyntaxError> = Vec::new();
 
-        let operator = tokens
-            .next()
-            .expect("Internal error: RepeaterParser.parse called with an empty token iterator");
-        match operator.name {
-            TokenName::Repeater => {
-                let nested_repeater_result = RepeaterParser::new(operator.clone()).parse(variables_in_scope, tokens);
-                match nested_repeater_result {
-                    Ok(nested_repeater) => subtree.add_child(nested_repeater),
-                    Err(suberrors) => suberrors.iter().for_each(|e| errors.push(e.clone())),
-                }
+        let repetition = match self.token.name {
+            TokenName::Until => self.until().map(|(target, max)| Repetition::Until(target, max)),
+            _ => self.count().map(Repetition::Count),
+        };
+        let repetition = match repetition {
+            Ok(repetition) => Some(repetition),
+            Err(error) => {
+                errors.push(error);
+                None
             }
-            TokenName::Plus | TokenName::Minus | TokenName::Stdout => {
-                let op = SyntaxTree::new(SyntaxTreeKind::UnaryOp, Some(operator.clone()));
-                subtree.add_child(op);
+        };
+
+        let Some(next) = peek_significant(tokens) else {
+            errors.push(SyntaxError::LineIncomplete {
+                span: self.token.span,
+                expected: REPEATABLE.to_vec(),
+            });
+            return Err(errors);
+        };
+        let repeated = match next.name {
+            // Variables are assign-once, so there's no point repeating an assignment.
+            TokenName::Variable | TokenName::Mut | TokenName::Set => None,
+            _ => operator(tokens, self.depth + 1),
+        };
+        let repeated = match repeated {
+            Some(Ok(repeated)) => Some(repeated),
+            Some(Err(suberrors)) => {
+                errors.extend(suberrors);
+                None
             }
-            _ => errors.push(SyntaxError::UnexpectedToken {
-                unexpected: operator.clone(),
-                message: String::from("RepeaterParser: expected UnaryOp"),
-            }),
+            None => {
+                errors.push(SyntaxError::UnexpectedToken {
+                    unexpected: next.clone(),
+                    expected: REPEATABLE.to_vec(),
+                });
+                None
+            }
+        };
+
+        match (repetition, repeated) {
+            (Some(repetition), Some(repeated)) if errors.is_empty() => {
+                let span = self.token.span.to(&repeated.span());
+                Ok(match repetition {
+                    Repetition::Count(count) => Op::Repeat(count, Box::new(repeated), span),
+                    Repetition::Until(target, max) => Op::Until(target, max, Box::new(repeated), span),
+                })
+            }
+            _ => Err(errors),
         }
+    }
+}
 
-        if errors.is_empty() {
-            Ok(subtree)
-        } else {
-            Err(errors)
+/// Parses the operators in a group, after its `(` has already been consumed.
+///
+/// Leaves an unexpected token in place, for the caller to recover from.
+#[derive(Debug, PartialEq)]
+pub struct GroupParser {
+    token: Token,
+    depth: usize,
+}
+
+impl GroupParser {
+    pub fn new(token: Token) -> Self {
+        GroupParser { token, depth: 0 }
+    }
+}
+
+impl SyntaxParser for GroupParser {
+    type Output = Op;
+
+    fn parse(
+        &mut self,
+        tokens: &mut Peekable<Iter<'_, Token>>,
+    ) -> Result<Op, Vec<SyntaxError>> {
+        let mut span = self.token.span;
+        let depth = self.depth + 1;
+        let ops = operators_until(tokens, TokenName::CloseParen, GROUPED, depth, &mut span)?;
+        Ok(Op::Group(ops, span))
+    }
+}
+
+/// Parses `?zero(a | b)`, after its `?zero` has already been consumed.
+///
+/// Leaves an unexpected token in place, for the caller to recover from.
+#[derive(Debug, PartialEq)]
+pub struct IfZeroParser {
+    token: Token,
+    depth: usize,
+}
+
+impl IfZeroParser {
+    pub fn new(token: Token) -> Self {
+        IfZeroParser { token, depth: 0 }
+    }
+}
+
+impl SyntaxParser for IfZeroParser {
+    type Output = Op;
+
+    fn parse(
+        &mut self,
+        tokens: &mut Peekable<Iter<'_, Token>>,
+    ) -> Result<Op, Vec<SyntaxError>> {
+        let mut span = self.token.span;
+        match peek_significant(tokens) {
+            Some(token) if token.name == TokenName::OpenParen => {
+                tokens.next();
+                span = span.to(&token.span);
+            }
+            Some(token) => {
+                return Err(vec![SyntaxError::UnexpectedToken {
+                    unexpected: token.clone(),
+                    expected: vec![TokenName::OpenParen],
+                }])
+            }
+            None => {
+                return Err(vec![SyntaxError::LineIncomplete {
+                    span,
+                    expected: vec![TokenName::OpenParen],
+                }])
+            }
+        }
+        let depth = self.depth + 1;
+        let if_zero = operators_until(tokens, TokenName::Pipe, FIRST_BRANCH, depth, &mut span)?;
+        let otherwise = operators_until(tokens, TokenName::CloseParen, GROUPED, depth, &mut span)?;
+        Ok(Op::IfZero(if_zero, otherwise, span))
+    }
+}
+
+/// Parses operators up to and including the token `end`, extending `span` over them.
+/// `expected` lists the tokens allowed in between, for error messages, and `depth` is
+/// how many groups, branches and repeaters they're inside.
+fn operators_until(
+    tokens: &mut Peekable<Iter<'_, Token>>,
+    end: TokenName,
+    expected: &[TokenName],
+    depth: usize,
+    span: &mut Span,
+) -> Result<Vec<Op>, Vec<SyntaxError>> {
+    let mut ops: Vec<Op> = Vec::new();
+    loop {
+        let Some(token) = peek_significant(tokens) else {
+            return Err(vec![SyntaxError::LineIncomplete {
+                span: *span,
+                expected: expected.to_vec(),
+            }]);
+        };
+        if token.name == end {
+            tokens.next();
+            *span = span.to(&token.span);
+            return Ok(ops);
+        }
+        match operator(tokens, depth) {
+            Some(Ok(op)) => {
+                *span = span.to(&op.span());
+                ops.push(op);
+            }
+            Some(Err(errors)) => return Err(errors),
+            None => {
+                return Err(vec![SyntaxError::UnexpectedToken {
+                    unexpected: token.clone(),
+                    expected: expected.to_vec(),
+                }])
+            }
         }
     }
 }
@@ -0,0 +1,475 @@
+use std::collections::HashMap;
+
+use crate::interpret::UType;
+use crate::lex::tokens::Token;
+use crate::module::{qualify, Imports};
+use crate::span::Span;
+use crate::syntax::ast::{Op, Program, RepeatCount, Source, Statement, UntilTarget};
+use crate::syntax::parser::{ProgramParser, SyntaxError};
+use crate::syntax::visit::{walk_op, Visitor};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SymbolKind {
+    /// `$name`
+    Variable,
+    /// `@name`
+    Operator,
+    /// The `name` in `IMPORT "path" AS name;`.
+    Module,
+}
+
+/// Everything known about one variable or named operator.
+///
+/// Variables and operators can share a name, since the `$` or `@` is part of it.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// Where the variable is assigned, the operator's name is defined or the import is
+    /// named. Everything an import brings in is defined at its name.
+    pub definition: Span,
+    /// Every statement source, repeat count, `SET` or operator call that refers to the
+    /// symbol, in order.
+    pub uses: Vec<Span>,
+    /// The type of the value the variable holds, or `None` for operators and when an
+    /// earlier error in the same statement means it can't be known.
+    pub utype: Option<UType>,
+    /// Whether `SET` may change the variable, because it was declared with `MUT`.
+    pub mutable: bool,
+    /// How many `BEGIN` blocks the symbol is defined inside.
+    pub depth: usize,
+}
+
+/// A `BEGIN;` that hasn't been closed by an `END;` yet.
+#[derive(Debug, Clone)]
+struct Block {
+    begin: Span,
+    /// The names defined in the block, and the symbols they shadow (if any), in order.
+    shadowed: Vec<(String, Option<usize>)>,
+}
+
+/// The variables and named operators defined by a program, built by name resolution
+/// after parsing.
+///
+/// A table can be carried from one program to the next, as the REPL does with each
+/// line it reads, so that later programs can use the variables of earlier ones (and
+/// close the blocks they opened).
+#[derive(Debug, Default, Clone)]
+pub struct SymbolTable {
+    /// In the order they were defined, including those that have gone out of scope.
+    symbols: Vec<Symbol>,
+    /// The symbols that are in scope.
+    by_name: HashMap<String, usize>,
+    blocks: Vec<Block>,
+    imports: Imports,
+}
+
+impl SymbolTable {
+    /// A table whose imports are relative to the current directory.
+    pub fn new() -> Self {
+        Self::with_imports(Imports::default())
+    }
+
+    pub fn with_imports(imports: Imports) -> Self {
+        SymbolTable {
+            symbols: Vec::new(),
+            by_name: HashMap::new(),
+            blocks: Vec::new(),
+            imports,
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Symbol> {
+        self.by_name.get(name).map(|index| &self.symbols[*index])
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.by_name.contains_key(name)
+    }
+
+    /// All the symbols, in the order they were defined, even if they've gone out of scope.
+    pub fn symbols(&self) -> &[Symbol] {
+        &self.symbols
+    }
+
+    /// The symbols that are in scope, in the order they were defined.
+    pub fn in_scope(&self) -> impl Iterator<Item = &Symbol> {
+        self.symbols
+            .iter()
+            .enumerate()
+            .filter(|(index, symbol)| self.by_name.get(&symbol.name) == Some(index))
+            .map(|(_, symbol)| symbol)
+    }
+
+    /// An error for each block that's been opened with `BEGIN;` but not closed.
+    pub fn unclosed_blocks(&self) -> Vec<SyntaxError> {
+        self.blocks
+            .iter()
+            .map(|block| SyntaxError::UnclosedBlock { span: block.begin })
+            .collect()
+    }
+
+    /// Finds the symbol defined or used at the byte offset `offset`, for tools like
+    /// go-to-definition.
+    pub fn symbol_at(&self, offset: usize) -> Option<&Symbol> {
+        let contains = |span: &Span| span.start <= offset && offset < span.end;
+        self.symbols
+            .iter()
+            .find(|symbol| contains(&symbol.definition) || symbol.uses.iter().any(contains))
+    }
+
+    /// Checks every variable and named operator in `program` against this table, adding
+    /// their definitions and uses. The table is only changed if there are no errors.
+    pub fn resolve(&mut self, program: &Program) -> Result<(), Vec<SyntaxError>> {
+        let mut resolved = self.clone();
+        let errors: Vec<SyntaxError> = program
+            .statements
+            .iter()
+            .flat_map(|statement| resolved.resolve_statement(statement))
+            .collect();
+        if errors.is_empty() {
+            *self = resolved;
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Like `resolve`, but for a single statement, and always records what it can.
+    pub fn resolve_statement(&mut self, statement: &Statement) -> Vec<SyntaxError> {
+        let mut errors = Vec::new();
+        match statement {
+            Statement::Chain { source, ops, .. } => {
+                let utype = match source {
+                    Source::Literal(value, _) => Some(value.utype()),
+                    Source::Var(name, span) => self.use_variable(name, *span, &mut errors),
+                };
+                self.resolve_ops(ops, utype, OpsContext::Statement, &mut errors);
+            }
+            Statement::Define {
+                name,
+                name_span,
+                ops,
+                ..
+            } => {
+                match self.get_in_block(name) {
+                    _ if name.contains('.') => errors.push(SyntaxError::QualifiedDefinition {
+                        name: name.clone(),
+                        span: *name_span,
+                    }),
+                    Some(symbol) => errors.push(SyntaxError::DefinedOperatorTwice {
+                        op_name: name.clone(),
+                        span: *name_span,
+                        first_defined: symbol.definition,
+                    }),
+                    None => self.define(name, SymbolKind::Operator, *name_span, None, false),
+                }
+                // Defined before its body, so that it can call itself.
+                self.resolve_ops(ops, None, OpsContext::Definition, &mut errors);
+            }
+            Statement::Begin { span } => self.blocks.push(Block {
+                begin: *span,
+                shadowed: Vec::new(),
+            }),
+            Statement::End { span } => match self.blocks.pop() {
+                Some(block) => {
+                    for (name, shadowed) in block.shadowed.into_iter().rev() {
+                        match shadowed {
+                            Some(index) => self.by_name.insert(name, index),
+                            None => self.by_name.remove(&name),
+                        };
+                    }
+                }
+                None => errors.push(SyntaxError::UnmatchedEnd { span: *span }),
+            },
+            Statement::Import {
+                path,
+                path_span,
+                alias,
+                alias_span,
+                ..
+            } => {
+                if let Some(symbol) = self.get_in_block(alias) {
+                    errors.push(SyntaxError::ImportedTwice {
+                        alias: alias.clone(),
+                        span: *alias_span,
+                        first_imported: symbol.definition,
+                    });
+                    return errors;
+                }
+                self.define(alias, SymbolKind::Module, *alias_span, None, false);
+                match self.imports.load(path) {
+                    Ok(module) => {
+                        let exported = module
+                            .symbols()
+                            .in_scope()
+                            .filter(|symbol| symbol.kind != SymbolKind::Module);
+                        for symbol in exported {
+                            if let Some(name) = qualify(alias, &symbol.name) {
+                                let (kind, utype) = (symbol.kind, symbol.utype);
+                                self.define(&name, kind, *alias_span, utype, symbol.mutable);
+                            }
+                        }
+                    }
+                    Err(error) => errors.push(SyntaxError::ImportFailed {
+                        path: path.clone(),
+                        reason: error.to_string(),
+                        span: *path_span,
+                    }),
+                }
+            }
+        }
+        errors
+    }
+
+    /// Like `get`, but only finds a symbol defined in the innermost block (or outside any
+    /// block, if none are open), which can't be defined again.
+    fn get_in_block(&self, name: &str) -> Option<&Symbol> {
+        self.get(name).filter(|symbol| symbol.depth == self.blocks.len())
+    }
+
+    fn define(
+        &mut self,
+        name: &str,
+        kind: SymbolKind,
+        definition: Span,
+        utype: Option<UType>,
+        mutable: bool,
+    ) {
+        let symbol = Symbol {
+            name: name.to_string(),
+            kind,
+            definition,
+            uses: Vec::new(),
+            utype,
+            mutable,
+            depth: self.blocks.len(),
+        };
+        let shadowed = self.by_name.insert(name.to_string(), self.symbols.len());
+        if let Some(block) = self.blocks.last_mut() {
+            block.shadowed.push((name.to_string(), shadowed));
+        }
+        self.symbols.push(symbol);
+    }
+
+    /// Records a use of the variable `name`, returning its type.
+    fn use_variable(
+        &mut self,
+        name: &str,
+        span: Span,
+        errors: &mut Vec<SyntaxError>,
+    ) -> Option<UType> {
+        match self.by_name.get(name) {
+            Some(index) => {
+                let symbol = &mut self.symbols[*index];
+                symbol.uses.push(span);
+                symbol.utype
+            }
+            None => {
+                errors.push(SyntaxError::UndeclaredVariable {
+                    var_name: name.to_string(),
+                    span,
+                });
+                None
+            }
+        }
+    }
+
+    /// Defines the variables assigned by `ops`, and checks the variables they `SET` and the
+    /// named operators they call.
+    fn resolve_ops(
+        &mut self,
+        ops: &[Op],
+        utype: Option<UType>,
+        context: OpsContext,
+        errors: &mut Vec<SyntaxError>,
+    ) {
+        let mut resolver = OpsResolver {
+            table: self,
+            utype,
+            context,
+            errors,
+        };
+        ops.iter().for_each(|op| resolver.visit_op(op));
+    }
+}
+
+/// Resolves the operators of one statement, which all apply to a value of type `utype`.
+/// Since variables are assign-once, an assignment that could run more than once (or not
+/// at all) is an error.
+struct OpsResolver<'a> {
+    table: &'a mut SymbolTable,
+    utype: Option<UType>,
+    context: OpsContext,
+    errors: &'a mut Vec<SyntaxError>,
+}
+
+impl OpsResolver<'_> {
+    /// Walks into `op` in `context`, unless it's already somewhere more restrictive than
+    /// a statement.
+    fn walk_within(&mut self, context: OpsContext, op: &Op) {
+        let outer = self.context;
+        if outer == OpsContext::Statement {
+            self.context = context;
+        }
+        walk_op(self, op);
+        self.context = outer;
+    }
+
+    fn assign(&mut self, name: &str, span: Span, mutable: bool) {
+        let var_name = name.to_string();
+        let error = match self.context {
+            OpsContext::Repeated => SyntaxError::AssignedInRepeat { var_name, span },
+            OpsContext::Definition => SyntaxError::AssignedInDefinition { var_name, span },
+            OpsContext::Conditional => SyntaxError::AssignedInConditional { var_name, span },
+            OpsContext::Statement => match self.table.get_in_block(name) {
+                _ if name.contains('.') => SyntaxError::QualifiedDefinition {
+                    name: var_name,
+                    span,
+                },
+                Some(symbol) => SyntaxError::AssignedVariableTwice {
+                    var_name,
+                    span,
+                    first_assigned: symbol.definition,
+                },
+                None => {
+                    let utype = self.utype;
+                    self.table.define(name, SymbolKind::Variable, span, utype, mutable);
+                    return;
+                }
+            },
+        };
+        self.errors.push(error);
+    }
+
+    fn rebind(&mut self, name: &str, span: Span) {
+        let Some(index) = self.table.by_name.get(name).copied() else {
+            self.errors.push(SyntaxError::UndeclaredVariable {
+                var_name: name.to_string(),
+                span,
+            });
+            return;
+        };
+        let symbol = &mut self.table.symbols[index];
+        symbol.uses.push(span);
+        if !symbol.mutable {
+            self.errors.push(SyntaxError::RebindImmutable {
+                var_name: name.to_string(),
+                span,
+                first_assigned: symbol.definition,
+            });
+        } else if let (Some(declared), Some(utype)) = (symbol.utype, self.utype) {
+            if declared != utype {
+                self.errors.push(SyntaxError::RebindTypeMismatch {
+                    var_name: name.to_string(),
+                    declared,
+                    utype,
+                    span,
+                });
+            }
+        }
+    }
+
+    /// Checks that the loop `{until target}` at `span` can reach its target.
+    fn until(&mut self, target: &UntilTarget, span: Span) {
+        let target_type = match target {
+            UntilTarget::Literal(value) => Some(value.utype()),
+            UntilTarget::Var(name, span) => self.table.use_variable(name, *span, self.errors),
+            UntilTarget::FixedPoint => self.utype,
+        };
+        if let (Some(target_type), Some(utype)) = (target_type, self.utype) {
+            if target_type != utype {
+                self.errors.push(SyntaxError::UnreachableTarget {
+                    target_type,
+                    utype,
+                    span,
+                });
+            }
+        }
+    }
+}
+
+impl Visitor for OpsResolver<'_> {
+    fn visit_op(&mut self, op: &Op) {
+        match op {
+            Op::Assign(name, span) => self.assign(name, *span, false),
+            Op::AssignMut(name, span) => self.assign(name, *span, true),
+            Op::Rebind(name, span) => self.rebind(name, *span),
+            Op::Call(name, span) => match self.table.by_name.get(name) {
+                Some(index) => self.table.symbols[*index].uses.push(*span),
+                None => self.errors.push(SyntaxError::UndefinedOperator {
+                    op_name: name.clone(),
+                    span: *span,
+                }),
+            },
+            Op::Repeat(..) => self.walk_within(OpsContext::Repeated, op),
+            Op::Until(target, _, _, span) => {
+                self.until(target, *span);
+                self.walk_within(OpsContext::Repeated, op)
+            }
+            Op::IfZero(..) => self.walk_within(OpsContext::Conditional, op),
+            _ => walk_op(self, op),
+        }
+    }
+
+    fn visit_repeat_count(&mut self, count: &RepeatCount) {
+        if let RepeatCount::Var(name, span) = count {
+            self.table.use_variable(name, *span, self.errors);
+        }
+    }
+
+    /// Already checked, with the loop it belongs to, by `until`.
+    fn visit_until_target(&mut self, _target: &UntilTarget) {}
+}
+
+/// Where a run of operators is, which decides whether they may assign variables.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum OpsContext {
+    Statement,
+    /// Inside a repeater, at any depth.
+    Repeated,
+    /// In a branch of `?zero`, at any depth, so that every variable is assigned exactly once.
+    Conditional,
+    /// In the body of a named operator, at any depth.
+    Definition,
+}
+
+/// Like `parse_and_resolve`, but for a whole file, which has to close every block it opens.
+pub fn parse_and_resolve_file(
+    tokens: &[Token],
+    symbols: &mut SymbolTable,
+) -> Result<Program, Vec<SyntaxError>> {
+    let program = parse_and_resolve(tokens, symbols)?;
+    let unclosed = symbols.unclosed_blocks();
+    if unclosed.is_empty() {
+        Ok(program)
+    } else {
+        Err(unclosed)
+    }
+}
+
+/// Parses `tokens`, then resolves the program against `symbols`.
+///
+/// Reports the parse and resolution errors together, in source order. Statements that
+/// failed to parse are left out of name resolution.
+pub fn parse_and_resolve(
+    tokens: &[Token],
+    symbols: &mut SymbolTable,
+) -> Result<Program, Vec<SyntaxError>> {
+    let mut token_iter = tokens.iter().peekable();
+    let (program, mut errors) = ProgramParser::new().parse_all(&mut token_iter);
+    let resolved = if errors.is_empty() {
+        symbols.resolve(&program)
+    } else {
+        symbols.clone().resolve(&program)
+    };
+    if let Err(resolve_errors) = resolved {
+        errors.extend(resolve_errors);
+        errors.sort_by_key(|error| error.span().start);
+    }
+    if errors.is_empty() {
+        Ok(program)
+    } else {
+        Err(errors)
+    }
+}
@@ -0,0 +1,145 @@
+use crate::interpret::UValue;
+use crate::span::Span;
+use crate::syntax::ast::{Op, Program, RepeatCount, Source, Statement, UntilTarget};
+
+/// Walks a program without changing it.
+///
+/// Each method is called on one kind of node, and by default goes on into the node's
+/// children by calling the `walk_` function of the same name. Override the methods for
+/// the nodes you're interested in, and call the `walk_` function from the override to
+/// keep going into their children, or don't to skip them:
+///
+/// ```
+/// use u::syntax::ast::Op;
+/// use u::syntax::visit::{walk_op, Visitor};
+///
+/// /// Counts the `STDOUT`s in a program, at any depth.
+/// struct Outputs(usize);
+///
+/// impl Visitor for Outputs {
+///     fn visit_op(&mut self, op: &Op) {
+///         if let Op::Stdout(_) = op {
+///             self.0 += 1;
+///         }
+///         walk_op(self, op);
+///     }
+/// }
+/// ```
+///
+/// Passes that look at the whole program, like name resolution, use this. Running it
+/// doesn't: `UInterpreter` applies each operator to the value from the one before,
+/// takes only one branch of `?zero`, repeats and reverses operators, and stops at the
+/// first error, none of which fits visiting each node once in order.
+pub trait Visitor {
+    fn visit_program(&mut self, program: &Program) {
+        walk_program(self, program)
+    }
+
+    fn visit_statement(&mut self, statement: &Statement) {
+        walk_statement(self, statement)
+    }
+
+    fn visit_source(&mut self, source: &Source) {
+        walk_source(self, source)
+    }
+
+    /// Called for every operator, including the ones inside repeaters, groups and
+    /// conditionals.
+    fn visit_op(&mut self, op: &Op) {
+        walk_op(self, op)
+    }
+
+    fn visit_repeat_count(&mut self, count: &RepeatCount) {
+        walk_repeat_count(self, count)
+    }
+
+    fn visit_until_target(&mut self, target: &UntilTarget) {
+        walk_until_target(self, target)
+    }
+
+    /// Called for literals, in sources and loop targets.
+    fn visit_value(&mut self, _value: &UValue) {}
+
+    /// Called for every span in the program.
+    fn visit_span(&mut self, _span: &Span) {}
+}
+
+pub fn walk_program<V: Visitor + ?Sized>(visitor: &mut V, program: &Program) {
+    for statement in &program.statements {
+        visitor.visit_statement(statement);
+    }
+}
+
+pub fn walk_statement<V: Visitor + ?Sized>(visitor: &mut V, statement: &Statement) {
+    visitor.visit_span(&statement.span());
+    match statement {
+        Statement::Chain { source, ops, .. } => {
+            visitor.visit_source(source);
+            ops.iter().for_each(|op| visitor.visit_op(op));
+        }
+        Statement::Define { name_span, ops, .. } => {
+            visitor.visit_span(name_span);
+            ops.iter().for_each(|op| visitor.visit_op(op));
+        }
+        Statement::Begin { .. } | Statement::End { .. } => {}
+        Statement::Import {
+            path_span,
+            alias_span,
+            ..
+        } => {
+            visitor.visit_span(path_span);
+            visitor.visit_span(alias_span);
+        }
+    }
+}
+
+pub fn walk_source<V: Visitor + ?Sized>(visitor: &mut V, source: &Source) {
+    match source {
+        Source::Literal(value, span) => {
+            visitor.visit_value(value);
+            visitor.visit_span(span);
+        }
+        Source::Var(_, span) => visitor.visit_span(span),
+    }
+}
+
+pub fn walk_op<V: Visitor + ?Sized>(visitor: &mut V, op: &Op) {
+    visitor.visit_span(&op.span());
+    match op {
+        Op::Inc(_)
+        | Op::Dec(_)
+        | Op::Stdout(_)
+        | Op::Assign(..)
+        | Op::AssignMut(..)
+        | Op::Rebind(..)
+        | Op::Call(..) => {}
+        Op::Repeat(count, repeated, _) => {
+            visitor.visit_repeat_count(count);
+            visitor.visit_op(repeated);
+        }
+        Op::Until(target, _, repeated, _) => {
+            visitor.visit_until_target(target);
+            visitor.visit_op(repeated);
+        }
+        Op::Group(ops, _) => ops.iter().for_each(|op| visitor.visit_op(op)),
+        Op::IfZero(if_zero, otherwise, _) => {
+            if_zero.iter().for_each(|op| visitor.visit_op(op));
+            otherwise.iter().for_each(|op| visitor.visit_op(op));
+        }
+    }
+}
+
+pub fn walk_repeat_count<V: Visitor + ?Sized>(visitor: &mut V, count: &RepeatCount) {
+    match count {
+        RepeatCount::Literal(_) => {}
+        RepeatCount::Var(_, span) => visitor.visit_span(span),
+    }
+}
+
+pub fn walk_until_target<V: Visitor + ?Sized>(visitor: &mut V, target: &UntilTarget) {
+    match target {
+        UntilTarget::Literal(value) => visitor.visit_value(value),
+        UntilTarget::Var(_, span) => visitor.visit_span(span),
+        UntilTarget::FixedPoint => {}
+    }
+}
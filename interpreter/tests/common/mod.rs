@@ -0,0 +1,23 @@
+/// A seeded xorshift generator, so that the randomized tests see the same inputs every run.
+pub struct Random(u64);
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        Random(seed)
+    }
+
+    /// A number from `0` up to, but not including, `n`.
+    pub fn below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % n as u64) as usize
+    }
+
+    /// Fewer than `max` of `pieces`, picked at random and joined together.
+    pub fn text(&mut self, pieces: &[&str], max: usize) -> String {
+        (0..self.below(max))
+            .map(|_| pieces[self.below(pieces.len())])
+            .collect()
+    }
+}
@@ -0,0 +1,225 @@
+mod common;
+
+use common::Random;
+use u::lex::LexAnalyzer;
+use u::syntax::ast::Statement;
+use u::syntax::cst::{CstElement, CstKind, CstNode};
+use u::syntax::parser::{ProgramParser, SyntaxParser, MAX_NESTING_DEPTH};
+
+fn assert_round_trips(input: &str) {
+    assert_eq!(CstNode::parse(input).to_string(), input);
+}
+
+/// The kinds of all of the nodes under `node`, including itself.
+fn kinds(node: &CstNode) -> Vec<CstKind> {
+    let mut found = vec![node.kind];
+    for child in &node.children {
+        if let CstElement::Node(child) = child {
+            found.extend(kinds(child));
+        }
+    }
+    found
+}
+
+/// How many nodes deep the tree under `node` goes, counting `node` itself.
+fn height(node: &CstNode) -> usize {
+    let children = node.children.iter().filter_map(|child| match child {
+        CstElement::Node(child) => Some(height(child)),
+        CstElement::Token(_) => None,
+    });
+    1 + children.max().unwrap_or(0)
+}
+
+/// `1`, then `open` repeated `depth` times around a `+`, with `close` repeated as often
+/// after it.
+fn nested(open: &str, close: &str, depth: usize) -> String {
+    format!("1 {}+{} STDOUT;", open.repeat(depth), close.repeat(depth))
+}
+
+/// Checks that the concrete syntax tree of a valid program has the same statements as the
+/// program the parser builds from it, and that none of it was left over as an error.
+fn assert_matches_parser(input: &str) {
+    let tokens = LexAnalyzer::new().lex_analysis(input).unwrap();
+    let program = ProgramParser::new()
+        .parse(&mut tokens.iter().peekable())
+        .unwrap();
+    let root = CstNode::parse(input);
+    assert_eq!(root.to_string(), input);
+    assert!(!kinds(&root).contains(&CstKind::Error), "in {:?}", input);
+
+    let statements: Vec<&CstNode> = root
+        .children
+        .iter()
+        .filter_map(|child| match child {
+            CstElement::Node(node) => Some(node),
+            CstElement::Token(_) => None,
+        })
+        .collect();
+    assert_eq!(statements.len(), program.statements.len(), "in {:?}", input);
+    for (node, statement) in statements.iter().zip(&program.statements) {
+        assert_eq!(node.kind, CstKind::Statement);
+        assert_eq!(node.span.end, statement.span().end, "in {:?}", input);
+        let head = node.children.iter().find_map(|child| match child {
+            CstElement::Node(head) => Some(head.kind),
+            CstElement::Token(_) => None,
+        });
+        let expected = match statement {
+            Statement::Chain { .. } => CstKind::Source,
+            Statement::Define { .. } => CstKind::Definition,
+            Statement::Begin { .. } | Statement::End { .. } => CstKind::Block,
+            Statement::Import { .. } => CstKind::Import,
+        };
+        assert_eq!(head, Some(expected), "in {:?}", input);
+    }
+}
+
+#[test]
+fn every_construct() {
+    let programs = [
+        "",
+        "1 STDOUT;",
+        "-0x1F + - STDOUT;",
+        "2.5 STDOUT;",
+        "'a' + STDOUT;",
+        "\"abc\" - STDOUT;",
+        "0 $x; $x STDOUT;",
+        "0 MUT $x; 1 SET $x; $x STDOUT;",
+        "@f := + +; 1 @f STDOUT;",
+        "1 {3} + STDOUT;",
+        "1 {2} {3} (+ -) STDOUT;",
+        "3 $n; 0 {$n} + STDOUT;",
+        "0 {until 4} + STDOUT;",
+        "0 {until fixed max 8} + STDOUT;",
+        "5 $x; 0 {until $x} + STDOUT;",
+        "1 (+ (- +)) STDOUT;",
+        "0 ?zero(+|-) STDOUT;",
+        "0 {2} ?zero(+ | (- -)) STDOUT;",
+        "BEGIN; 1 $x; $x STDOUT; END;",
+        "IMPORT \"lib.u\" AS lib; $lib.x @lib.f STDOUT;",
+        "'λ' + STDOUT; \"é\" $é; $é STDOUT;",
+    ];
+    for program in programs {
+        assert_round_trips(program);
+    }
+}
+
+#[test]
+fn comments_and_whitespace() {
+    let programs = [
+        "   ",
+        "\n\n\t\n",
+        "# only a comment",
+        "// only a comment\n",
+        "/* only a comment */",
+        "  1   +\tSTDOUT ;  ",
+        "1 STDOUT;\n\n\n2 STDOUT;\n",
+        "# leading\n1 STDOUT; # trailing\n/* after */",
+        "1 /* in */ + // line\n STDOUT;",
+        "@f /* a */ := /* b */ + ;",
+        "IMPORT /* a */ \"lib.u\" /* b */ AS /* c */ lib /* d */ ;",
+        "0 MUT /* a */ $x;",
+        "0 {2} /* a */ ( /* b */ + /* c */ ) ;",
+        "0 ?zero /* a */ ( + /* b */ | # c\n - ) ;",
+        "BEGIN ; \n  1 STDOUT ;\nEND ;\r\n",
+    ];
+    for program in programs {
+        assert_round_trips(program);
+    }
+}
+
+#[test]
+fn malformed_input() {
+    let programs = [
+        "1 + STDOUT",
+        ";",
+        ";;; 1 ;",
+        "1 ~ STDOUT;",
+        "1 + STDOUT; ~~~ ^^^",
+        "\"unterminated",
+        "1 \"unterminated STDOUT;\n2 STDOUT;",
+        "'",
+        "1 /* unterminated STDOUT;",
+        "1 {3 + STDOUT;",
+        "1 {} + STDOUT;",
+        "1 {until} + STDOUT;",
+        "1 {x} + STDOUT;",
+        "1 ( + STDOUT;",
+        "1 ) + STDOUT;",
+        "1 ((( STDOUT;",
+        "1 | + STDOUT;",
+        "1 ?zero + STDOUT;",
+        "1 ?zero( + STDOUT;",
+        "1 ?zero(+|-|+) STDOUT;",
+        "1 MUT STDOUT;",
+        "1 SET;",
+        "1 {2};",
+        "1 {2} {3}",
+        "@f;",
+        "@f :=",
+        ":= + ;",
+        "IMPORT;",
+        "IMPORT \"lib.u\";",
+        "IMPORT \"lib.u\" AS;",
+        "IMPORT AS lib;",
+        "BEGIN 1 STDOUT; END",
+        "+ + STDOUT;",
+        "STDOUT",
+        "1 0x 0xZZ 1.2.3 99999999999999999999 STDOUT;",
+        "$ @ ? { } 'ab' é",
+    ];
+    for program in programs {
+        assert_round_trips(program);
+    }
+}
+
+#[test]
+fn random_input() {
+    let pieces = [
+        "(", ")", "|", "{", "}", "1", "-2", "0x1f", "2.5", " ", "\n", ";", "+", "-", "STDOUT",
+        "$x", "@f", ":=", "{3}", "{$x}", "{until 3}", "'a'", "'", "\"s\"", "\"", "# c\n",
+        "// c", "/*", "*/", "?zero", "MUT", "SET", "BEGIN", "END", "IMPORT", "AS", "é", "~",
+    ];
+    let mut random = Random::new(0xc57);
+    for _ in 0..200 {
+        assert_round_trips(&random.text(&pieces, 30));
+    }
+}
+
+#[test]
+fn agrees_with_the_parser() {
+    let programs = [
+        "",
+        "# nothing but a comment\n",
+        "1 STDOUT; 2 STDOUT;",
+        "-0x1F + - STDOUT; 2.5 STDOUT; 'a' + STDOUT; \"abc\" - STDOUT;",
+        "0 MUT $x; 1 SET $x; $x STDOUT;",
+        "@f := + /* twice */ +; 1 @f STDOUT;",
+        "1 {2} {3} (+ -) STDOUT; 0 {until fixed max 8} + STDOUT;",
+        "5 $x; 0 {until $x} {$x} + STDOUT;",
+        "0 {2} ?zero(+ | (- ?zero(+|-))) STDOUT;",
+        "BEGIN;\n  1 $x;\n  BEGIN; 2 $x; END;\nEND;\n# done",
+        "IMPORT \"lib.u\" AS lib; $lib.x @lib.f STDOUT;",
+        "'λ' + STDOUT; \"é\" $é; $é STDOUT;",
+    ];
+    for program in programs {
+        assert_matches_parser(program);
+    }
+}
+
+#[test]
+fn nests_as_deeply_as_the_parser_allows() {
+    for (open, close) in [("(", ")"), ("{1} ", ""), ("{until fixed} ", ""), ("?zero(|", ")")] {
+        assert_matches_parser(&nested(open, close, MAX_NESTING_DEPTH));
+    }
+}
+
+#[test]
+fn leaves_deeper_nesting_as_errors() {
+    for (open, close) in [("(", ")"), ("{1} ", ""), ("?zero(|", ")"), ("({2} ?zero(", "))")] {
+        let input = nested(open, close, 100_000);
+        let root = CstNode::parse(&input);
+        assert_eq!(root.to_string(), input);
+        assert!(kinds(&root).contains(&CstKind::Error), "in {:?}", open);
+        assert!(height(&root) <= 2 * MAX_NESTING_DEPTH + 4, "in {:?}", open);
+    }
+}
@@ -0,0 +1,154 @@
+mod common;
+
+use std::fs;
+
+use common::Random;
+use u::document::{Document, TextEdit};
+use u::module::Imports;
+
+/// Applies each `(start, end, replacement)` edit to a document opened on `text`, checking
+/// after every one that it matches a document opened fresh on the edited text.
+fn apply_edits(text: &str, edits: &[(usize, usize, &str)]) -> Document {
+    let mut document = Document::new(text);
+    let mut expected = text.to_string();
+    for &(start, end, replacement) in edits {
+        expected.replace_range(start..end, replacement);
+        let update = document.apply_edit(TextEdit {
+            range: start..end,
+            replacement: replacement.to_string(),
+        });
+        assert_eq!(document.text(), expected);
+        assert_eq!(update.program, document.program());
+        assert_matches_fresh(&document);
+    }
+    document
+}
+
+fn assert_matches_fresh(document: &Document) {
+    let fresh = Document::new(document.text());
+    assert_eq!(document.program(), fresh.program(), "in {:?}", document.text());
+    assert_eq!(
+        diagnostics(document),
+        diagnostics(&fresh),
+        "in {:?}",
+        document.text()
+    );
+}
+
+fn diagnostics(document: &Document) -> Vec<String> {
+    document
+        .diagnostics()
+        .iter()
+        .map(|diagnostic| format!("{:?}", diagnostic))
+        .collect()
+}
+
+/// Types `text` into an empty document one character at a time.
+fn typing(text: &str) -> Vec<(usize, usize, &str)> {
+    text.char_indices()
+        .map(|(offset, c)| (offset, offset, &text[offset..offset + c.len_utf8()]))
+        .collect()
+}
+
+#[test]
+fn typing_a_program() {
+    let document = apply_edits("", &typing("0 $x; @f := + +;\n$x {until 4} @f STDOUT;"));
+    assert!(document.diagnostics().is_empty());
+    assert_eq!(document.program().statements.len(), 3);
+}
+
+#[test]
+fn edits_within_a_token() {
+    let text = "1 + STDOUT; 2 - STDOUT;";
+    apply_edits(text, &[(5, 5, " "), (5, 6, ""), (16, 16, "x"), (2, 3, "-")]);
+}
+
+#[test]
+fn edits_across_token_and_statement_boundaries() {
+    let text = "1 + STDOUT; 2 - STDOUT; 3 STDOUT;";
+    apply_edits(text, &[(2, 7, "{3} ("), (8, 15, ")"), (4, 20, "")]);
+    // Joining two statements by deleting the `;` between them, then splitting them again.
+    apply_edits(text, &[(10, 11, ""), (10, 10, ";")]);
+}
+
+#[test]
+fn moves_the_spans_of_later_statements() {
+    let text = "1 STDOUT;\n'λ' + STDOUT;\n$y;";
+    apply_edits(text, &[(0, 0, "0 $y;\n"), (0, 1, "12345"), (10, 10, "é")]);
+}
+
+#[test]
+fn unterminated_strings_and_comments() {
+    let text = "1 STDOUT; 2 STDOUT; 3 STDOUT;";
+    // Opening a string swallows the rest of the text, until it's closed again.
+    apply_edits(text, &[(2, 2, "\""), (2, 3, ""), (12, 12, "\""), (18, 18, "\"")]);
+    apply_edits(text, &[(0, 0, "/* "), (15, 15, " */"), (0, 3, "")]);
+    apply_edits(text, &[(9, 9, " # comment"), (9, 19, ""), (0, 0, "'")]);
+}
+
+#[test]
+fn deleting_everything() {
+    let text = "BEGIN; 0 MUT $x; /* one */ 1 SET $x; END;";
+    let document = apply_edits(text, &[(0, text.len(), "")]);
+    assert_eq!(document.program().statements.len(), 0);
+    assert!(document.diagnostics().is_empty());
+    apply_edits(text, &[(0, text.len(), ""), (0, 0, "1 STDOUT;")]);
+}
+
+#[test]
+fn keeps_imported_files_loaded_across_edits() {
+    let dir = std::env::temp_dir().join(format!("u-document-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("lib.u"), "2 $x;").unwrap();
+    let main = dir.join("main.u");
+    let text = "IMPORT \"lib.u\" AS lib; $lib.x STDOUT;";
+    fs::write(&main, text).unwrap();
+    let imports = || Imports::for_file(main.to_str().unwrap()).unwrap();
+
+    let mut document = Document::with_imports(text, imports());
+    assert!(document.diagnostics().is_empty());
+    // Once it's been loaded, the file isn't read again, so editing the document still finds
+    // the variables it had then.
+    fs::write(dir.join("lib.u"), "2 $y;").unwrap();
+    document.apply_edit(TextEdit {
+        range: text.len()..text.len(),
+        replacement: " $lib.x + STDOUT;".to_string(),
+    });
+    let fresh = Document::with_imports(document.text(), imports());
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(document.diagnostics().is_empty(), "{:?}", diagnostics(&document));
+    assert_eq!(document.symbols().get("$lib.x").unwrap().uses.len(), 2);
+    assert_eq!(diagnostics(&fresh).len(), 2);
+}
+
+#[test]
+fn random_edits() {
+    let pieces = [
+        "(", ")", "1", " ", "\n", ";", "+", "STDOUT", "$x", "{3}", "'a'", "\"s\"", "# c\n",
+        "/*", "*/", "\"", "@f := ", "?zero(+|-)", "{until $x}", "BEGIN;", "END;", "MUT $x",
+        "SET $x", "é",
+    ];
+    let mut random = Random::new(0x5eed);
+    for _ in 0..10 {
+        let text = random.text(&pieces, 20);
+        let mut edited = text.clone();
+        let mut edits: Vec<(usize, usize, String)> = Vec::new();
+        for _ in 0..8 {
+            let boundaries: Vec<usize> = (0..=edited.len())
+                .filter(|offset| edited.is_char_boundary(*offset))
+                .collect();
+            let start = boundaries[random.below(boundaries.len())];
+            let end = boundaries[random.below(boundaries.len())];
+            let (start, end) = (start.min(end), start.max(end));
+            let replacement = random.text(&pieces, 3);
+            edited.replace_range(start..end, &replacement);
+            edits.push((start, end, replacement));
+        }
+        let edits: Vec<(usize, usize, &str)> = edits
+            .iter()
+            .map(|(start, end, replacement)| (*start, *end, replacement.as_str()))
+            .collect();
+        apply_edits(&text, &edits);
+    }
+}
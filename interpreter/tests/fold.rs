@@ -0,0 +1,105 @@
+use u::interpret::UValue;
+use u::lex::LexAnalyzer;
+use u::syntax::ast::{Op, Program, Statement};
+use u::syntax::fold::{fold_op, fold_ops, Folder};
+use u::syntax::parser::{ProgramParser, SyntaxParser};
+
+fn parse(input: &str) -> Program {
+    let tokens = LexAnalyzer::new().lex_analysis(input).unwrap();
+    ProgramParser::new()
+        .parse(&mut tokens.iter().peekable())
+        .unwrap()
+}
+
+/// Swaps every `+` for a `-`, and the other way around.
+struct Invert;
+
+impl Folder for Invert {
+    fn fold_op(&mut self, op: Op) -> Op {
+        match fold_op(self, op) {
+            Op::Inc(span) => Op::Dec(span),
+            Op::Dec(span) => Op::Inc(span),
+            op => op,
+        }
+    }
+}
+
+#[test]
+fn rewrites_operators_at_any_depth() {
+    // Swapping `+` and `-` keeps every span the same, so the result is what parsing the
+    // swapped text gives.
+    let input = "1 + {2} (- ?zero(+ | - (+ {until 5} -))) STDOUT; @f := {3} - +; 0 @f;";
+    let swapped: String = input
+        .chars()
+        .map(|c| match c {
+            '+' => '-',
+            '-' => '+',
+            c => c,
+        })
+        .collect();
+    assert_eq!(Invert.fold_program(parse(input)), parse(&swapped));
+}
+
+/// Adds one to every integer literal.
+struct Increment;
+
+impl Folder for Increment {
+    fn fold_value(&mut self, value: UValue) -> UValue {
+        match value {
+            UValue::Integer(int) => UValue::Integer(int + 1),
+            value => value,
+        }
+    }
+}
+
+#[test]
+fn rewrites_the_values_of_sources_and_loop_targets() {
+    let input = "1 {until 5} + STDOUT; 'a' ?zero(({until 3} -) | +); 2 {3} +;";
+    let expected = "2 {until 6} + STDOUT; 'a' ?zero(({until 4} -) | +); 3 {3} +;";
+    assert_eq!(Increment.fold_program(parse(input)), parse(expected));
+}
+
+/// Removes every `STDOUT`, however deeply it's nested.
+struct Quiet;
+
+impl Folder for Quiet {
+    fn fold_ops(&mut self, ops: Vec<Op>) -> Vec<Op> {
+        fold_ops(self, ops)
+            .into_iter()
+            .filter(|op| !matches!(op, Op::Stdout(_)))
+            .collect()
+    }
+}
+
+#[test]
+fn removes_operators_from_each_run() {
+    let input = "1 STDOUT + (STDOUT ?zero(STDOUT | - STDOUT)) {2} (STDOUT +) STDOUT;";
+    let folded = Quiet.fold_program(parse(input));
+    let [statement] = &folded.statements[..] else {
+        panic!("expected one statement, got {:?}", folded.statements);
+    };
+    let Statement::Chain { ops, .. } = statement else {
+        panic!("expected a chain, got {:?}", statement);
+    };
+    let [Op::Inc(_), Op::Group(group, _), Op::Repeat(_, repeated, _)] = &ops[..] else {
+        panic!("unexpected operators {:?}", ops);
+    };
+    let [Op::IfZero(if_zero, otherwise, _)] = &group[..] else {
+        panic!("unexpected group {:?}", group);
+    };
+    assert!(if_zero.is_empty());
+    assert!(matches!(&otherwise[..], [Op::Dec(_)]));
+    let Op::Group(repeated, _) = repeated.as_ref() else {
+        panic!("unexpected repeated operator {:?}", repeated);
+    };
+    assert!(matches!(&repeated[..], [Op::Inc(_)]));
+}
+
+#[test]
+fn leaves_a_program_unchanged_by_default() {
+    struct Identity;
+    impl Folder for Identity {}
+
+    let input = "BEGIN; 0 MUT $x; 1 SET $x; @f := {$x} ?zero(+|-); END; IMPORT \"a.u\" AS a;";
+    assert_eq!(Identity.fold_program(parse(input)), parse(input));
+}
@@ -0,0 +1,211 @@
+use std::fs;
+use std::ops::Range;
+
+use u::interpret::UType;
+use u::lex::LexAnalyzer;
+use u::module::Imports;
+use u::syntax::resolver::{parse_and_resolve, SymbolKind, SymbolTable};
+
+fn resolve(input: &str) -> SymbolTable {
+    resolve_with(input, SymbolTable::new())
+}
+
+fn resolve_with(input: &str, mut symbols: SymbolTable) -> SymbolTable {
+    let tokens = LexAnalyzer::new().lex_analysis(input).unwrap();
+    parse_and_resolve(&tokens, &mut symbols).unwrap();
+    symbols
+}
+
+/// The byte range of the `nth` (from 0) occurrence of `needle` in `input`.
+fn find(input: &str, needle: &str, nth: usize) -> Range<usize> {
+    let (start, _) = input.match_indices(needle).nth(nth).unwrap();
+    start..start + needle.len()
+}
+
+/// The name of the symbol at each byte of `range`, which should be the same for all of them.
+fn name_at(symbols: &SymbolTable, range: Range<usize>) -> Option<String> {
+    let names: Vec<Option<String>> = range
+        .map(|offset| symbols.symbol_at(offset).map(|symbol| symbol.name.clone()))
+        .collect();
+    assert!(names.windows(2).all(|pair| pair[0] == pair[1]), "{:?}", names);
+    names[0].clone()
+}
+
+fn ranges(spans: &[u::span::Span]) -> Vec<Range<usize>> {
+    spans.iter().map(|span| span.start..span.end).collect()
+}
+
+fn in_scope(symbols: &SymbolTable) -> Vec<(String, usize)> {
+    symbols
+        .in_scope()
+        .map(|symbol| (symbol.name.clone(), symbol.depth))
+        .collect()
+}
+
+#[test]
+fn definitions_and_uses() {
+    let input = "1 $x; $x + $y; @f := + -; $y @f {$x} @f STDOUT;";
+    let symbols = resolve(input);
+
+    let x = symbols.get("$x").unwrap();
+    assert_eq!(x.kind, SymbolKind::Variable);
+    assert_eq!(x.definition.start..x.definition.end, find(input, "$x", 0));
+    assert_eq!(
+        ranges(&x.uses),
+        vec![find(input, "$x", 1), find(input, "$x", 2)]
+    );
+    let y = symbols.get("$y").unwrap();
+    assert_eq!(y.definition.start..y.definition.end, find(input, "$y", 0));
+    assert_eq!(ranges(&y.uses), vec![find(input, "$y", 1)]);
+    let f = symbols.get("@f").unwrap();
+    assert_eq!(f.kind, SymbolKind::Operator);
+    assert_eq!(f.definition.start..f.definition.end, find(input, "@f", 0));
+    assert_eq!(
+        ranges(&f.uses),
+        vec![find(input, "@f", 1), find(input, "@f", 2)]
+    );
+
+    for nth in 0..3 {
+        assert_eq!(name_at(&symbols, find(input, "$x", nth)), Some("$x".into()));
+        assert_eq!(name_at(&symbols, find(input, "@f", nth)), Some("@f".into()));
+    }
+    for nth in 0..2 {
+        assert_eq!(name_at(&symbols, find(input, "$y", nth)), Some("$y".into()));
+    }
+    // Only the names themselves belong to a symbol.
+    assert_eq!(symbols.symbol_at(find(input, "1", 0).start), None);
+    assert_eq!(symbols.symbol_at(find(input, ";", 0).start), None);
+    assert_eq!(symbols.symbol_at(find(input, ":=", 0).start), None);
+    assert_eq!(symbols.symbol_at(find(input, "{", 0).start), None);
+    assert_eq!(symbols.symbol_at(find(input, "$x", 0).end), None);
+    assert_eq!(symbols.symbol_at(input.len()), None);
+}
+
+#[test]
+fn types() {
+    let input = "1 $i; 2.5 $f; 'a' + $l; \"s\" $s; $i + $j; 0 MUT $m; @op := +;";
+    let symbols = resolve(input);
+    let utype = |name: &str| symbols.get(name).unwrap().utype;
+    assert_eq!(utype("$i"), Some(UType::Integer));
+    assert_eq!(utype("$f"), Some(UType::Float));
+    assert_eq!(utype("$l"), Some(UType::Letter));
+    assert_eq!(utype("$s"), Some(UType::UString));
+    assert_eq!(utype("$j"), Some(UType::Integer));
+    assert_eq!(utype("$m"), Some(UType::Integer));
+    assert_eq!(utype("@op"), None);
+
+    let m = symbols.get("$m").unwrap();
+    assert!(m.mutable);
+    assert_eq!(m.definition.start..m.definition.end, find(input, "MUT $m", 0));
+    assert!(!symbols.get("$i").unwrap().mutable);
+}
+
+#[test]
+fn shadowing_across_blocks() {
+    let input = "1 $x; @f := +; BEGIN; 'a' $x; @f := -; $x @f STDOUT; 2 $y; END; $x @f STDOUT;";
+    let symbols = resolve(input);
+
+    // The inner definitions are kept, but only the outer ones are in scope after `END;`.
+    let xs: Vec<_> = symbols.symbols().iter().filter(|s| s.name == "$x").collect();
+    assert_eq!(xs.len(), 2);
+    assert_eq!((xs[0].depth, xs[0].utype), (0, Some(UType::Integer)));
+    assert_eq!((xs[1].depth, xs[1].utype), (1, Some(UType::Letter)));
+    assert_eq!(ranges(&xs[0].uses), vec![find(input, "$x", 3)]);
+    assert_eq!(ranges(&xs[1].uses), vec![find(input, "$x", 2)]);
+    assert_eq!(symbols.get("$x"), Some(xs[0]));
+    assert_eq!(symbols.get("$y"), None);
+    assert_eq!(
+        in_scope(&symbols),
+        vec![("$x".to_string(), 0), ("@f".to_string(), 0)]
+    );
+
+    // Each name is found as whichever symbol was in scope where it's written.
+    let definition_at = |range: Range<usize>| symbols.symbol_at(range.start).unwrap().definition;
+    assert_eq!(definition_at(find(input, "$x", 3)), xs[0].definition);
+    assert_eq!(definition_at(find(input, "$x", 2)), xs[1].definition);
+    assert_eq!(definition_at(find(input, "@f", 2)), definition_at(find(input, "@f", 1)));
+    assert_eq!(definition_at(find(input, "@f", 3)), definition_at(find(input, "@f", 0)));
+    assert_ne!(definition_at(find(input, "@f", 3)), definition_at(find(input, "@f", 1)));
+}
+
+#[test]
+fn in_scope_inside_an_open_block() {
+    let input = "1 $x; 2 $y; BEGIN; 'a' $x; BEGIN; 3 $z;";
+    let symbols = resolve(input);
+    assert_eq!(
+        in_scope(&symbols),
+        vec![
+            ("$y".to_string(), 0),
+            ("$x".to_string(), 1),
+            ("$z".to_string(), 2),
+        ]
+    );
+    assert_eq!(symbols.unclosed_blocks().len(), 2);
+
+    // Closing the blocks in a later program, as the REPL does.
+    let symbols = resolve_with("END; $x STDOUT; END;", symbols);
+    assert_eq!(
+        in_scope(&symbols),
+        vec![("$x".to_string(), 0), ("$y".to_string(), 0)]
+    );
+    let uses: Vec<usize> = symbols
+        .symbols()
+        .iter()
+        .filter(|s| s.name == "$x")
+        .map(|s| s.uses.len())
+        .collect();
+    assert_eq!(uses, vec![0, 1]);
+}
+
+#[test]
+fn imported_symbols() {
+    let dir = std::env::temp_dir().join(format!("u-symbols-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("lib.u"),
+        "2 $x; 'q' MUT $m; @f := +; IMPORT \"other.u\" AS other; BEGIN; 3 $hidden; END;",
+    )
+    .unwrap();
+    fs::write(dir.join("other.u"), "1 $o;").unwrap();
+    let main = dir.join("main.u");
+    let input = "IMPORT \"lib.u\" AS lib; $lib.x @lib.f STDOUT; 'r' SET $lib.m; $lib.x STDOUT;";
+    fs::write(&main, input).unwrap();
+
+    let imports = Imports::for_file(main.to_str().unwrap()).unwrap();
+    let symbols = resolve_with(input, SymbolTable::with_imports(imports));
+    fs::remove_dir_all(&dir).unwrap();
+
+    let alias = find(input, "AS lib", 0);
+    let module = symbols.get("lib").unwrap();
+    assert_eq!(module.kind, SymbolKind::Module);
+    assert_eq!(module.definition.start..module.definition.end, alias);
+
+    // Everything the file defines outside any block, and nothing it imports itself, is
+    // defined at the alias.
+    let mut names: Vec<String> = in_scope(&symbols).into_iter().map(|(name, _)| name).collect();
+    names.sort();
+    assert_eq!(names, vec!["$lib.m", "$lib.x", "@lib.f", "lib"]);
+    for name in ["$lib.x", "$lib.m", "@lib.f"] {
+        let symbol = symbols.get(name).unwrap();
+        assert_eq!(symbol.definition.start..symbol.definition.end, alias, "{}", name);
+    }
+
+    let x = symbols.get("$lib.x").unwrap();
+    assert_eq!((x.kind, x.utype, x.mutable), (SymbolKind::Variable, Some(UType::Integer), false));
+    assert_eq!(
+        ranges(&x.uses),
+        vec![find(input, "$lib.x", 0), find(input, "$lib.x", 1)]
+    );
+    let m = symbols.get("$lib.m").unwrap();
+    assert_eq!((m.utype, m.mutable), (Some(UType::Letter), true));
+    // `SET` is part of the use, as `MUT` is part of a definition.
+    assert_eq!(ranges(&m.uses), vec![find(input, "SET $lib.m", 0)]);
+    let f = symbols.get("@lib.f").unwrap();
+    assert_eq!((f.kind, f.utype), (SymbolKind::Operator, None));
+    assert_eq!(ranges(&f.uses), vec![find(input, "@lib.f", 0)]);
+
+    assert_eq!(name_at(&symbols, find(input, "$lib.x", 1)), Some("$lib.x".into()));
+    assert_eq!(name_at(&symbols, find(input, "@lib.f", 0)), Some("@lib.f".into()));
+    // The alias, with its `AS`, is where the module and everything in it is defined.
+    assert_eq!(name_at(&symbols, alias), Some("lib".into()));
+}
@@ -0,0 +1,138 @@
+use u::interpret::UValue;
+use u::lex::LexAnalyzer;
+use u::span::Span;
+use u::syntax::ast::{Op, Program, RepeatCount, UntilTarget};
+use u::syntax::parser::{ProgramParser, SyntaxParser};
+use u::syntax::visit::{walk_op, walk_repeat_count, walk_until_target, Visitor};
+
+fn parse(input: &str) -> Program {
+    let tokens = LexAnalyzer::new().lex_analysis(input).unwrap();
+    ProgramParser::new()
+        .parse(&mut tokens.iter().peekable())
+        .unwrap()
+}
+
+/// Records the source text of every operator, value and span it's called on, in order.
+struct Recorder<'a> {
+    input: &'a str,
+    ops: Vec<&'a str>,
+    values: Vec<UValue>,
+    spans: Vec<&'a str>,
+    counts: usize,
+    targets: usize,
+}
+
+impl<'a> Recorder<'a> {
+    fn new(input: &'a str) -> Self {
+        Recorder {
+            input,
+            ops: Vec::new(),
+            values: Vec::new(),
+            spans: Vec::new(),
+            counts: 0,
+            targets: 0,
+        }
+    }
+
+    fn run(input: &'a str) -> Self {
+        let mut recorder = Recorder::new(input);
+        recorder.visit_program(&parse(input));
+        recorder
+    }
+}
+
+impl Visitor for Recorder<'_> {
+    fn visit_op(&mut self, op: &Op) {
+        let span = op.span();
+        self.ops.push(&self.input[span.start..span.end]);
+        walk_op(self, op);
+    }
+
+    fn visit_repeat_count(&mut self, count: &RepeatCount) {
+        self.counts += 1;
+        walk_repeat_count(self, count);
+    }
+
+    fn visit_until_target(&mut self, target: &UntilTarget) {
+        self.targets += 1;
+        walk_until_target(self, target);
+    }
+
+    fn visit_value(&mut self, value: &UValue) {
+        self.values.push(value.clone());
+    }
+
+    fn visit_span(&mut self, span: &Span) {
+        self.spans.push(&self.input[span.start..span.end]);
+    }
+}
+
+#[test]
+fn visits_every_operator_in_order() {
+    let input = "1 {2} (+ ?zero(- | STDOUT (+))) {until 5} - $x;";
+    let recorder = Recorder::run(input);
+    assert_eq!(
+        recorder.ops,
+        vec![
+            "{2} (+ ?zero(- | STDOUT (+)))",
+            "(+ ?zero(- | STDOUT (+)))",
+            "+",
+            "?zero(- | STDOUT (+))",
+            "-",
+            "STDOUT",
+            "(+)",
+            "+",
+            "{until 5} -",
+            "-",
+            "$x",
+        ]
+    );
+    assert_eq!((recorder.counts, recorder.targets), (1, 1));
+    assert_eq!(recorder.values, vec![UValue::Integer(1), UValue::Integer(5)]);
+}
+
+#[test]
+fn visits_every_statement_and_span() {
+    let input = "BEGIN; 'a' $x; @f := {$n} +; IMPORT \"lib.u\" AS lib; END;";
+    let recorder = Recorder::run(input);
+    assert_eq!(recorder.ops, vec!["$x", "{$n} +", "+"]);
+    assert_eq!(
+        recorder.spans,
+        vec![
+            "BEGIN;",
+            "'a' $x;",
+            "'a'",
+            "$x",
+            "@f := {$n} +;",
+            "@f",
+            "{$n} +",
+            "$n",
+            "+",
+            "IMPORT \"lib.u\" AS lib;",
+            "\"lib.u\"",
+            "AS lib",
+            "END;",
+        ]
+    );
+    assert_eq!(recorder.values, vec![UValue::Letter('a')]);
+}
+
+/// Only counts the operators outside any group or conditional, by not walking into them.
+struct TopLevel(usize);
+
+impl Visitor for TopLevel {
+    fn visit_op(&mut self, op: &Op) {
+        self.0 += 1;
+        if !matches!(op, Op::Group(..) | Op::IfZero(..)) {
+            walk_op(self, op);
+        }
+    }
+}
+
+#[test]
+fn skips_the_children_of_nodes_it_does_not_walk() {
+    let mut top_level = TopLevel(0);
+    top_level.visit_program(&parse("1 + (+ +) ?zero(+ | -) {3} (- -) {2} +;"));
+    // `+`, the group, `?zero`, `{3}` and its group, `{2}` and its `+`.
+    assert_eq!(top_level.0, 7);
+}
@@ -0,0 +1,186 @@
+#shellcheck shell=sh
+Describe 'BEGIN/END blocks'
+  It 'may shadow a variable from outside them'
+    echo '1 $x; BEGIN; 2 $x; $x STDOUT; END; $x STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The line 1 of stdout should eq '2'
+    The line 2 of stdout should eq '1'
+  End
+
+  It 'may be nested'
+    echo "'a' \$x; BEGIN; 'b' \$x; BEGIN; 'c' \$x; \$x STDOUT; END; \$x STDOUT; END; \$x STDOUT;" >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The lines of stdout should eq 3
+    The line 1 of stdout should eq 'c'
+    The line 2 of stdout should eq 'b'
+    The line 3 of stdout should eq 'a'
+  End
+
+  It 'can read variables from outside them'
+    echo '1 $x; BEGIN; $x + STDOUT; END;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The stdout should eq '2'
+  End
+
+  It 'take their variables out of scope at END'
+    echo 'BEGIN; 1 $x; END; $x STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The stdout should eq ''
+    The first line of stderr should eq 'Syntax analysis failed!'
+    The line 2 of stderr should include '1:19: Variable not declared: $x'
+  End
+
+  It 'take their operators out of scope at END'
+    echo 'BEGIN; @f := +; END; 0 @f;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The first line of stderr should eq 'Syntax analysis failed!'
+    The line 2 of stderr should include '1:24: Operator not defined: @f'
+  End
+
+  It 'still assign each variable once'
+    echo 'BEGIN; 1 $x; 2 $x; END;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The first line of stderr should eq 'Syntax analysis failed!'
+    The line 2 of stderr should include '1:16: Cannot assign to this variable twice: $x (first assigned at 1:10)'
+  End
+
+  It 'return a syntax error for END without BEGIN'
+    echo '1 STDOUT; END;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The stdout should eq ''
+    The first line of stderr should eq 'Syntax analysis failed!'
+    The line 2 of stderr should include '1:11: END without a matching BEGIN'
+  End
+
+  It 'return a syntax error for BEGIN without END'
+    echo 'BEGIN; 1 STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The stdout should eq ''
+    The first line of stderr should eq 'Syntax analysis failed!'
+    The line 2 of stderr should include '1:1: BEGIN without a matching END'
+  End
+
+  It 'suggest the keyword when it is in the wrong case'
+    echo 'begin; END;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The first line of stderr should eq 'Lexical analysis failed!'
+    The line 2 of stderr should include 'did you mean BEGIN?'
+  End
+End
+
+Describe 'Named operators in blocks'
+  It 'use the variables in scope where they were defined'
+    echo '1 $n; @f := {$n} +; BEGIN; 5 $n; 0 @f STDOUT; END;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The stdout should eq '1'
+  End
+
+  It 'may be shadowed'
+    echo '@f := +; BEGIN; @f := -; 0 @f STDOUT; END; 0 @f STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The line 1 of stdout should eq '-1'
+    The line 2 of stdout should eq '1'
+  End
+End
+
+Describe 'MUT and SET'
+  It 'change the value of a variable'
+    echo '1 MUT $x; 5 SET $x; $x STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The stdout should eq '5'
+  End
+
+  It 'may be used in a loop'
+    echo '0 MUT $total; 1 {4} (+ STDOUT SET $total); $total STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The lines of stdout should eq 5
+    The line 5 of stdout should eq '5'
+  End
+
+  It 'change a variable from outside a block'
+    echo "'a' MUT \$x; BEGIN; 'q' SET \$x; END; \$x STDOUT;" >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The stdout should eq 'q'
+  End
+
+  It 'may be used by named operators'
+    echo '0 MUT $count; @tick := + SET $count; $count @tick; $count @tick; $count STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The stdout should eq '2'
+  End
+
+  It 'return a syntax error for a variable not declared with MUT'
+    echo '1 $x; 2 SET $x;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The first line of stderr should eq 'Syntax analysis failed!'
+    The line 2 of stderr should include "1:9: Cannot SET a variable that wasn't declared with MUT: \$x (first assigned at 1:3)"
+  End
+
+  It 'return a syntax error for an undeclared variable'
+    echo '2 SET $x;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The first line of stderr should eq 'Syntax analysis failed!'
+    The line 2 of stderr should include '1:3: Variable not declared: $x'
+  End
+
+  It 'return a syntax error for a value of another type'
+    echo '1 MUT $x; 2.5 SET $x;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The first line of stderr should eq 'Syntax analysis failed!'
+    The line 2 of stderr should include '1:15: Cannot SET $x to a value of type Float, it was declared with type Integer'
+  End
+
+  It 'return a runtime error for a value of another type in a named operator'
+    echo '1 MUT $x; @f := SET $x; 2.5 @f;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The first line of stderr should eq 'Execution failed!'
+    The line 2 of stderr should include '1:17: Cannot SET $x to a value of type Float, it was declared with type Integer'
+  End
+
+  It 'cannot be used in a repeater to declare a variable'
+    echo '1 {2} MUT $x;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The first line of stderr should eq 'Syntax analysis failed!'
+    The line 2 of stderr should include "Unexpected token: Mut 'MUT'"
+  End
+End
@@ -0,0 +1,66 @@
+#shellcheck shell=sh
+Describe 'comments'
+  It 'can be a # line comment'
+    echo "# increments five" >> $program
+    echo "5 + STDOUT; # prints 6" >> $program
+    When call $U_INTERPRETER $program
+    The stdout should eq '6'
+    The status should be success
+  End
+
+  It 'can be a // line comment'
+    echo "// increments five" >> $program
+    echo "5 + STDOUT; // prints 6" >> $program
+    When call $U_INTERPRETER $program
+    The stdout should eq '6'
+    The status should be success
+  End
+
+  It 'can occur in the middle of a statement'
+    echo "5 + # one" >> $program
+    echo "  + // two" >> $program
+    echo "  STDOUT;" >> $program
+    When call $U_INTERPRETER $program
+    The stdout should eq '7'
+    The status should be success
+  End
+
+  It 'can be a block comment'
+    echo "5 /* add one */ + STDOUT;" >> $program
+    When call $U_INTERPRETER $program
+    The stdout should eq '6'
+    The status should be success
+  End
+
+  It 'can be a block comment spanning several lines'
+    echo "/* this block" >> $program
+    echo "   1 + STDOUT;" >> $program
+    echo "   is ignored */" >> $program
+    echo "2 + STDOUT;" >> $program
+    When call $U_INTERPRETER $program
+    The stdout should eq '3'
+    The status should be success
+  End
+
+  It 'can contain tokens that would otherwise be invalid'
+    echo "1 STDOUT; # ~ @@@ 'ab' \"" >> $program
+    When call $U_INTERPRETER $program
+    The stdout should eq '1'
+    The status should be success
+  End
+
+  It 'is not recognized inside a string'
+    echo '"a # b" STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+    The stdout should eq 'a # b'
+    The status should be success
+  End
+
+  It 'can comment out the end of a statement'
+    echo "1 + # STDOUT;" >> $program
+    When call $U_INTERPRETER $program
+    The status should be failure
+    The line 1 of stderr should eq 'Syntax analysis failed!'
+    The line 2 of stderr should include 'Expected more tokens before end of line'
+  End
+End
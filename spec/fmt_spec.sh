@@ -0,0 +1,106 @@
+#shellcheck shell=sh
+Describe 'u fmt'
+  format_program() { $U_INTERPRETER fmt "$@" "$program" && cat "$program"; }
+  format_and_run() { $U_INTERPRETER fmt "$program" && $U_INTERPRETER "$program"; }
+  format_twice() { $U_INTERPRETER fmt "$program" && $U_INTERPRETER fmt --check "$program"; }
+
+  It 'puts each statement on its own line, with single spaces'
+    echo '"hello"   {13}+ STDOUT ;@f:=+  - ; 0 ?zero(+|  -)(+ +)STDOUT;' >> $program
+    When call format_program
+
+    The status should be success
+    The lines of stdout should eq 3
+    The line 1 of stdout should eq '"hello" {13} + STDOUT;'
+    The line 2 of stdout should eq '@f := + -;'
+    The line 3 of stdout should eq '0 ?zero(+ | -) (+ +) STDOUT;'
+  End
+
+  It 'indents the statements in blocks'
+    echo 'BEGIN; 1 MUT $x; BEGIN; 2 SET $x; END; END; IMPORT  "lib.u"  AS  lib;' >> $program
+    When call format_program
+
+    The status should be success
+    The line 1 of stdout should eq 'BEGIN;'
+    The line 2 of stdout should eq '  1 MUT $x;'
+    The line 3 of stdout should eq '  BEGIN;'
+    The line 4 of stdout should eq '    2 SET $x;'
+    The line 5 of stdout should eq '  END;'
+    The line 6 of stdout should eq 'END;'
+    The line 7 of stdout should eq 'IMPORT "lib.u" AS lib;'
+  End
+
+  It 'keeps comments where they are'
+    printf '# increments\n\n\n\n5 + # one\n  + /* two */ STDOUT;   // prints 7\n' >> $program
+    When call format_program
+
+    The status should be success
+    The lines of stdout should eq 4
+    The line 1 of stdout should eq '# increments'
+    The line 2 of stdout should eq ''
+    The line 3 of stdout should eq '5 + # one'
+    The line 4 of stdout should eq '    + /* two */ STDOUT; // prints 7'
+  End
+
+  It 'does not change what the program does'
+    printf '"hello"{13}+ # rot13\nSTDOUT;' >> $program
+    When call format_and_run
+
+    The status should be success
+    The stdout should eq 'uryyb'
+  End
+
+  It 'collapses runs of + and - into repeaters with --collapse'
+    echo '0 + + + - - STDOUT + STDOUT ?zero(+ + | -);' >> $program
+    When call format_program --collapse
+
+    The status should be success
+    The stdout should eq '0 {3} + {2} - STDOUT + STDOUT ?zero({2} + | -);'
+  End
+
+  It 'expands repeaters of + and - with --expand'
+    echo '0 {3} + {-2} + {$n} + {100} - {2} STDOUT;' >> $program
+    When call format_program --expand
+
+    The status should be success
+    The stdout should eq '0 + + + - - {$n} + {100} - {2} STDOUT;'
+  End
+
+  It 'does not change a formatted file'
+    printf '  # one\n1 $x;/* two */BEGIN;\n$x {until 9  max 20 }+ STDOUT;\n\n\nEND; // three\n' >> $program
+    When call format_twice
+
+    The status should be success
+    The stdout should eq ''
+  End
+
+  It 'fails with --check if the file would change'
+    echo '1  STDOUT;' >> $program
+    When call $U_INTERPRETER fmt --check $program
+
+    The status should be failure
+    The stdout should eq "$program"
+  End
+
+  It 'succeeds with --check if the file would not change'
+    echo '1 STDOUT;' >> $program
+    When call $U_INTERPRETER fmt --check $program
+
+    The status should be success
+    The stdout should eq ''
+  End
+
+  It 'returns the syntax errors of a program that does not parse'
+    echo '1 STDOUT' >> $program
+    When call $U_INTERPRETER fmt $program
+
+    The status should be failure
+    The first line of stderr should eq 'Syntax analysis failed!'
+  End
+
+  It 'needs a file'
+    When call $U_INTERPRETER fmt --check
+
+    The status should be failure
+    The stderr should eq 'usage: u fmt [--check] [--collapse | --expand] hello.u...'
+  End
+End
@@ -6,4 +6,19 @@ Describe 'random characters'
     The status should be failure
     The first line of stderr should include "Lexical analysis failed!"
   End
+
+  It 'random operators on random values do not crash the interpreter'
+    values="0 -1 9223372036854775807 2.5 nan 'a' '!' 'Z' \"Hi,_there!\" \"\""
+    ops="+,-,STDOUT,{3},{-2},(+),?zero(-|+),{until fixed max 5}"
+    awk -v values="$values" -v ops="$ops" 'BEGIN {
+      srand(); nv = split(values, v, " "); no = split(ops, o, ",");
+      for (i = 0; i < 50; i++) {
+        line = v[int(rand() * nv) + 1];
+        for (j = int(rand() * 6); j > 0; j--) line = line " " o[int(rand() * no) + 1] " +";
+        print line ";";
+      }
+    }' >> $program
+    When call $U_INTERPRETER $program
+    The stderr should not include 'panicked'
+  End
 End
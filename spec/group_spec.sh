@@ -0,0 +1,103 @@
+#shellcheck shell=sh
+Describe '() groups'
+  It 'apply their operators in order'
+    echo "5 (+ + STDOUT) STDOUT;" >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The first line of stdout should eq '7'
+    The second line of stdout should eq '7'
+  End
+
+  It 'can be repeated as a unit'
+    echo "0 {5} (+ STDOUT);" >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The lines of stdout should eq 5
+    The first line of stdout should eq '1'
+    The line 5 of stdout should eq '5'
+  End
+
+  It 'can be nested'
+    echo "0 {2} (+ {3} (+ STDOUT));" >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The lines of stdout should eq 6
+    The third line of stdout should eq '4'
+    The line 6 of stdout should eq '8'
+  End
+
+  It 'can be nested 256 deep'
+    printf '1 %s+%s STDOUT;\n' "$(printf '%.0s(' $(seq 256))" "$(printf '%.0s)' $(seq 256))" >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The stdout should eq '2'
+  End
+
+  It 'returns a syntax error when they are nested too deeply, rather than crashing'
+    printf '1 %s+%s STDOUT;\n' "$(printf '%.0s(' $(seq 100000))" "$(printf '%.0s)' $(seq 100000))" >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The stdout should eq ''
+    The first line of stderr should eq 'Syntax analysis failed!'
+    The line 2 of stderr should include '1:259: Operators nested too deeply (more than 256 levels)'
+  End
+
+  It 'can be empty'
+    echo "'a' () {3} () STDOUT;" >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The stdout should eq 'a'
+  End
+
+  It 'can assign variables when they are not repeated'
+    echo '"a" (+ $b +) $c;' >> $program
+    echo '$b STDOUT;' >> $program
+    echo '$c STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The first line of stdout should eq 'b'
+    The second line of stdout should eq 'c'
+  End
+
+  It 'cannot assign variables when they are repeated'
+    echo '0 {2} (+ $x);' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The first line of stderr should eq 'Syntax analysis failed!'
+    The line 2 of stderr should eq "$program:1:10: Cannot assign to a variable inside a repeated group: \$x"
+  End
+
+  It 'cannot assign variables when they are nested inside a repeated group'
+    echo '0 {2} ((+ $x));' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The line 2 of stderr should include 'Cannot assign to a variable inside a repeated group: $x'
+  End
+
+  It 'reports a missing closing parenthesis'
+    echo '0 {2} (+ STDOUT;' >> $program
+    echo '1 STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The lines of stderr should eq 2
+    The line 2 of stderr should include "1:16: Unexpected token: Semicolon ';', expected +, -, STDOUT, {n}, {until x}, (, ?zero, \$var, MUT, SET, @op or )"
+  End
+
+  It 'reports a stray closing parenthesis'
+    echo '0 + ) STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The line 2 of stderr should include "1:5: Unexpected token: CloseParen ')'"
+  End
+End
@@ -0,0 +1,102 @@
+#shellcheck shell=sh
+Describe '?zero conditionals'
+  Parameters
+    '0' '1'
+    '7' '6'
+    '0.0' '1'
+    '-2.5' '-3.5'
+    "'a'" 'b'
+    "'A'" 'B'
+    "'α'" 'β'
+    "'c'" 'b'
+    "'0'" '1'
+    "'5'" '4'
+    '""' ''
+    '"abc"' 'zab'
+  End
+
+  It "takes the first branch only for zero values, like $1"
+    echo "$1 ?zero(+ | -) STDOUT;" >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The stdout should eq "$2"
+  End
+End
+
+Describe '?zero'
+  It 'can have empty branches'
+    echo '0 ?zero( | -) STDOUT; 3 ?zero(+ | ) STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The first line of stdout should eq '0'
+    The second line of stdout should eq '3'
+  End
+
+  It 'can be nested and repeated'
+    echo '3 {5} ?zero(STDOUT + + | ?zero( | -)) STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The lines of stdout should eq 2
+    The first line of stdout should eq '0'
+    The second line of stdout should eq '1'
+  End
+
+  It 'applies the inverse of the branch it takes under a negative count'
+    echo '0 {-1} ?zero(+ | -) STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The stdout should eq '-1'
+  End
+
+  It 'runs a counter automaton through a recursive named operator'
+    # Counts down to zero, printing each state, then halts.
+    echo '@countdown := STDOUT ?zero( | - @countdown);' >> $program
+    echo '3 @countdown;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The lines of stdout should eq 4
+    The first line of stdout should eq '3'
+    The line 4 of stdout should eq '0'
+  End
+
+  It 'cannot assign variables in its branches'
+    echo '0 ?zero(+ | $x);' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The first line of stderr should eq 'Syntax analysis failed!'
+    The line 2 of stderr should include '1:13: Cannot assign to a variable inside a ?zero branch: $x'
+  End
+
+  It 'needs both branches'
+    echo '0 ?zero(+);' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The first line of stderr should eq 'Syntax analysis failed!'
+    The line 2 of stderr should include "1:10: Unexpected token: CloseParen ')', expected +, -, STDOUT, {n}, {until x}, (, ?zero, \$var, MUT, SET, @op or |"
+  End
+
+  It 'needs parentheses'
+    echo '0 ?zero + | -;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The first line of stderr should eq 'Syntax analysis failed!'
+    The line 2 of stderr should include "1:9: Unexpected token: Plus '+', expected ("
+  End
+
+  It 'suggests ?zero for a misspelling'
+    echo '0 ?Zero(+ | -);' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The first line of stderr should eq 'Lexical analysis failed!'
+    The line 2 of stderr should include 'Unknown token: ?Zero (did you mean ?zero?)'
+  End
+End
@@ -0,0 +1,177 @@
+#shellcheck shell=sh
+Describe 'IMPORT'
+  It 'makes the variables of another file available'
+    echo '13 $key; "hello" $greeting;' > "$program_dir/import_vars.u"
+    echo 'IMPORT "import_vars.u" AS lib; $lib.greeting STDOUT; $lib.key + STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The line 1 of stdout should eq 'hello'
+    The line 2 of stdout should eq '14'
+  End
+
+  It 'makes the named operators of another file available'
+    echo '@rot13 := {13} +; @twice := @rot13 @rot13;' > "$program_dir/import_ops.u"
+    echo 'IMPORT "import_ops.u" AS c; "hello" @c.rot13 STDOUT @c.twice STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The line 1 of stdout should eq 'uryyb'
+    The line 2 of stdout should eq 'uryyb'
+  End
+
+  It 'runs the imported file once, where it is imported'
+    echo '"imported" STDOUT;' > "$program_dir/import_once.u"
+    echo '"first" STDOUT; IMPORT "import_once.u" AS a; IMPORT "import_once.u" AS b; "last" STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The lines of stdout should eq 3
+    The line 1 of stdout should eq 'first'
+    The line 2 of stdout should eq 'imported'
+    The line 3 of stdout should eq 'last'
+  End
+
+  It 'allows repeat counts and loop targets from another file'
+    echo '3 $n; 5 $end;' > "$program_dir/import_counts.u"
+    echo 'IMPORT "import_counts.u" AS k; 0 {$k.n} + STDOUT {until $k.end} + STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The line 1 of stdout should eq '3'
+    The line 2 of stdout should eq '5'
+  End
+
+  It 'resolves paths relative to the importing file'
+    mkdir -p "$program_dir/import_lib"
+    echo '@double := + +;' > "$program_dir/import_lib/util.u"
+    echo 'IMPORT "util.u" AS u; @quadruple := @u.double @u.double;' > "$program_dir/import_lib/math.u"
+    echo 'IMPORT "import_lib/math.u" AS m; 0 @m.quadruple STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The stdout should eq '4'
+  End
+
+  It 'shares MUT variables with the imported file'
+    echo '0 MUT $count; @bump := + SET $count;' > "$program_dir/import_mut.u"
+    echo 'IMPORT "import_mut.u" AS m; $m.count @m.bump; $m.count @m.bump; $m.count STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The stdout should eq '2'
+  End
+
+  It 'does not pass on what the imported file imported'
+    echo '1 $x;' > "$program_dir/import_inner.u"
+    echo 'IMPORT "import_inner.u" AS inner;' > "$program_dir/import_outer.u"
+    echo 'IMPORT "import_outer.u" AS outer; $outer.x STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The first line of stderr should eq 'Syntax analysis failed!'
+    The line 2 of stderr should include '1:35: Variable not declared: $outer.x'
+  End
+
+  It 'returns a syntax error for a file that does not exist'
+    echo 'IMPORT "import_missing.u" AS m;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The first line of stderr should eq 'Syntax analysis failed!'
+    The line 2 of stderr should include '1:8: Cannot import "import_missing.u": No such file or directory'
+  End
+
+  It 'returns a syntax error for an import cycle'
+    echo 'IMPORT "import_cycle_b.u" AS b;' > "$program_dir/import_cycle_a.u"
+    echo 'IMPORT "import_cycle_a.u" AS a;' > "$program_dir/import_cycle_b.u"
+    echo 'IMPORT "import_cycle_a.u" AS a;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The first line of stderr should eq 'Syntax analysis failed!'
+    The line 2 of stderr should include '1:8: Cannot import "import_cycle_a.u": syntax analysis failed'
+    The line 4 of stderr should include 'import_cycle_b.u:1:8: Cannot import "import_cycle_a.u": it imports itself'
+  End
+
+  It 'returns a syntax error for a file that imports the importing file'
+    echo "IMPORT \"$(basename "$program")\" AS main;" > "$program_dir/import_back.u"
+    echo 'IMPORT "import_back.u" AS b;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The first line of stderr should eq 'Syntax analysis failed!'
+    The line 3 of stderr should include 'it imports itself'
+  End
+
+  It 'returns the errors of the imported file'
+    echo '1 $x; $y;' > "$program_dir/import_broken.u"
+    echo 'IMPORT "import_broken.u" AS b;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The first line of stderr should eq 'Syntax analysis failed!'
+    The line 2 of stderr should include '1:8: Cannot import "import_broken.u": syntax analysis failed'
+    The line 3 of stderr should include 'import_broken.u:1:7: Variable not declared: $y'
+  End
+
+  It 'returns a runtime error if the imported file fails'
+    echo '0 {until -1 max 3} +;' > "$program_dir/import_fails.u"
+    echo '"before" STDOUT; IMPORT "import_fails.u" AS f;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The stdout should eq 'before'
+    The first line of stderr should eq 'Execution failed!'
+    The line 2 of stderr should include 'Cannot import "import_fails.u": it failed to run'
+    The line 3 of stderr should include 'import_fails.u:1:3: Loop did not reach its target within 3 iterations'
+  End
+
+  It 'returns a syntax error for a name that is not in the imported file'
+    echo '1 $x;' > "$program_dir/import_names.u"
+    echo 'IMPORT "import_names.u" AS n; $n.y STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The first line of stderr should eq 'Syntax analysis failed!'
+    The line 2 of stderr should include 'Variable not declared: $n.y'
+  End
+
+  It 'returns a syntax error for the same name imported twice'
+    echo '1 $x;' > "$program_dir/import_twice.u"
+    echo 'IMPORT "import_twice.u" AS n; IMPORT "import_twice.u" AS n;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The first line of stderr should eq 'Syntax analysis failed!'
+    The line 2 of stderr should include '1:55: Cannot import twice as the same name: n (first imported at 1:25)'
+  End
+
+  It 'returns a syntax error for assigning an imported name'
+    echo '1 $x;' > "$program_dir/import_assign.u"
+    echo 'IMPORT "import_assign.u" AS n; 2 $n.x;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The first line of stderr should eq 'Syntax analysis failed!'
+    The line 2 of stderr should include '1:34: Only imported variables and operators can have a . in their name: $n.x'
+  End
+
+  It 'needs a name after AS'
+    echo 'IMPORT "lib.u" AS;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The first line of stderr should eq 'Lexical analysis failed!'
+    The line 2 of stderr should include '1:16: Unknown token: AS (expected a name after AS, like AS lib)'
+  End
+
+  It 'needs AS'
+    echo 'IMPORT "lib.u";' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The first line of stderr should eq 'Syntax analysis failed!'
+    The line 2 of stderr should include "1:15: Unexpected token: Semicolon ';', expected AS name"
+  End
+End
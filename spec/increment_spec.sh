@@ -46,4 +46,13 @@ Describe '+ operator'
     The third line of stdout should eq '104'
     The status should be success
   End
+
+  It 'returns a runtime error when the integer overflows'
+    echo "1 STDOUT; 9223372036854775807 + STDOUT;" >> $program
+    When call $U_INTERPRETER $program
+    The stdout should eq '1'
+    The status should be failure
+    The first line of stderr should eq 'Execution failed!'
+    The line 2 of stderr should include '1:31: Integer overflow: 9223372036854775807 + 1 is out of range'
+  End
 End
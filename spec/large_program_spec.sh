@@ -0,0 +1,19 @@
+#shellcheck shell=sh
+Describe 'large programs'
+  It 'can contain tens of thousands of statements'
+    seq 50000 | sed 's/.*/1 +;/' >> $program
+    echo '0 {3} + STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+    The status should be success
+    The stdout should eq '3'
+  End
+
+  It 'can contain a very long single statement'
+    printf '0' >> $program
+    seq 50000 | sed 's/.*/ +/' | tr -d '\n' >> $program
+    echo ' STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+    The status should be success
+    The stdout should eq '50000'
+  End
+End
@@ -86,4 +86,109 @@ Describe 'letters'
     The line 9 of stdout should eq 'y'
     The line 10 of stdout should eq 'q'
   End
+
+  Describe 'non-latin alphabets'
+    It 'can increment a greek letter'
+      echo "'λ' + STDOUT;" >> $program
+      When call $U_INTERPRETER $program
+      The stdout should eq 'μ'
+      The status should be success
+    End
+
+    It 'can increment a greek letter and overflow'
+      echo "'ω' + STDOUT;" >> $program
+      When call $U_INTERPRETER $program
+      The stdout should eq 'α'
+      The status should be success
+    End
+
+    It 'can decrement an uppercase cyrillic letter and underflow'
+      echo "'А' - STDOUT;" >> $program
+      When call $U_INTERPRETER $program
+      The stdout should eq 'Я'
+      The status should be success
+    End
+
+    It 'moves a final sigma like any other sigma'
+      echo "'ς' + STDOUT; 'ς' - STDOUT; \"ας\" + STDOUT;" >> $program
+      When call $U_INTERPRETER $program
+      The line 1 of stdout should eq 'τ'
+      The line 2 of stdout should eq 'ρ'
+      The line 3 of stdout should eq 'βτ'
+      The status should be success
+    End
+
+    It 'leaves letters without a known alphabet unchanged'
+      echo "'ß' + STDOUT;" >> $program
+      When call $U_INTERPRETER $program
+      The stdout should eq 'ß'
+      The status should be success
+    End
+
+    It 'can rot13 a greek string'
+      echo '"αβγ" {13} + STDOUT;' >> $program
+      When call $U_INTERPRETER $program
+      The stdout should eq 'ξοπ'
+      The status should be success
+    End
+  End
+
+  Describe 'digits'
+    It 'can be incremented and overflow'
+      echo "'5' + STDOUT; '9' + STDOUT;" >> $program
+      When call $U_INTERPRETER $program
+      The line 1 of stdout should eq '6'
+      The line 2 of stdout should eq '0'
+      The status should be success
+    End
+
+    It 'can be decremented past zero'
+      echo "'5' {10} - STDOUT; '5' {7} - STDOUT;" >> $program
+      When call $U_INTERPRETER $program
+      The line 1 of stdout should eq '5'
+      The line 2 of stdout should eq '8'
+      The status should be success
+    End
+  End
+
+  Describe 'punctuation and symbols'
+    It 'are left unchanged'
+      echo "'!' + STDOUT; ',' {13} - STDOUT; '👾' + STDOUT;" >> $program
+      When call $U_INTERPRETER $program
+      The line 1 of stdout should eq '!'
+      The line 2 of stdout should eq ','
+      The line 3 of stdout should eq '👾'
+      The status should be success
+    End
+  End
+
+  Describe 'literals'
+    It 'can be any unicode scalar'
+      echo "'👾' STDOUT;" >> $program
+      When call $U_INTERPRETER $program
+      The stdout should eq '👾'
+      The status should be success
+    End
+
+    It 'can be an escaped quote'
+      printf '%s\n' "'\\'' STDOUT;" >> $program
+      When call $U_INTERPRETER $program
+      The stdout should eq "'"
+      The status should be success
+    End
+
+    It 'can be a unicode escape'
+      printf '%s\n' "'\\u{3bb}' STDOUT;" >> $program
+      When call $U_INTERPRETER $program
+      The stdout should eq 'λ'
+      The status should be success
+    End
+
+    It 'cannot contain more than one character'
+      echo "'ab' STDOUT;" >> $program
+      When call $U_INTERPRETER $program
+      The status should be failure
+      The line 1 of stderr should eq 'Lexical analysis failed!'
+    End
+  End
 End
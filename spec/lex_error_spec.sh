@@ -1,19 +1,20 @@
 #shellcheck shell=sh
 Describe 'lexer single error report'
+  # The lexer reports only the bad part of the input, then resumes at the next valid token
   Parameters
-    '~'
-    '#foo'
-    '&asdf'
-    '@@@@'
-    '/a'
-    '*Z'
-    '$1.50'
-    '$20'
-    ',000'
-    '_000'
-    '.23E5'
-    'Ω'
-    '👾'
+    '~' '~'
+    '%foo' '%foo'
+    '&asdf' '&asdf'
+    '@@@@' '@@@@'
+    '/a' '/a'
+    '*Z' '*Z'
+    '$1.50' 'Unknown token: $ ('
+    '$20' 'Unknown token: $ ('
+    ',000' 'Unknown token: ,'
+    '_000' '_000'
+    '.23E5' 'Unknown token: .'
+    'Ω' 'Ω'
+    '👾' '👾'
   End
 
   It "reports [$1] as not a valid token in the middle of a line"
@@ -24,7 +25,7 @@ Describe 'lexer single error report'
     The lines of stderr should eq 2
     The line 1 of stderr should eq "Lexical analysis failed!"
     The line 2 of stderr should include "Unknown token"
-    The line 2 of stderr should include $1
+    The line 2 of stderr should include "$2"
   End
 
   It "reports [$1] as not a valid token at the beginning of a line"
@@ -35,7 +36,7 @@ Describe 'lexer single error report'
     The lines of stderr should eq 2
     The line 1 of stderr should eq "Lexical analysis failed!"
     The line 2 of stderr should include "Unknown token"
-    The line 2 of stderr should include $1
+    The line 2 of stderr should include "$2"
   End
 
   It "reports [$1] as not a valid token at the end of a line"
@@ -46,7 +47,7 @@ Describe 'lexer single error report'
     The lines of stderr should eq 2
     The line 1 of stderr should eq "Lexical analysis failed!"
     The line 2 of stderr should include "Unknown token"
-    The line 2 of stderr should include $1
+    The line 2 of stderr should include "$2"
   End
 End
 
@@ -55,8 +56,8 @@ Describe 'lexer multi error report'
     echo "5 + + STDOUT;" >> $program
     echo "@@@@ + + STDOUT;" >> $program
     echo "6 + + STDOUT;" >> $program
-    echo "##### + + STDOUT;" >> $program
-    echo "//// + + STDOUT;" >> $program
+    echo "%%%%% + + STDOUT;" >> $program
+    echo "^^^^ + + STDOUT;" >> $program
     echo "7 + + STDOUT;" >> $program
     When call $U_INTERPRETER $program
     The stdout should be blank
@@ -64,8 +65,8 @@ Describe 'lexer multi error report'
     The lines of stderr should eq 4
     The line 1 of stderr should eq "Lexical analysis failed!"
     The line 2 of stderr should include "@@@@"
-    The line 3 of stderr should include "#####"
-    The line 4 of stderr should include "////"
+    The line 3 of stderr should include "%%%%%"
+    The line 4 of stderr should include "^^^^"
   End
 End
 
@@ -80,3 +81,88 @@ Describe 'characters'
     The line 2 of stderr should include "Unknown token: A"
   End
 End
+
+Describe 'lexer error locations'
+  It "reports the file, line and column of an unknown token"
+    echo "5 + STDOUT;" >> $program
+    echo "6 +  ~ STDOUT;" >> $program
+    When call $U_INTERPRETER $program
+    The status should be failure
+    The line 2 of stderr should eq "$program:2:6: Unknown token: ~"
+  End
+End
+
+Describe 'lexer error recovery'
+  It "reports each bad lexeme on a line separately"
+    echo "1 + ~ + @@ STDOUT;" >> $program
+    When call $U_INTERPRETER $program
+    The status should be failure
+    The lines of stderr should eq 3
+    The line 2 of stderr should eq "$program:1:5: Unknown token: ~"
+    The line 3 of stderr should eq "$program:1:9: Unknown token: @@"
+  End
+
+  It "resumes at the next valid token"
+    echo "1 +~+ STDOUT;" >> $program
+    When call $U_INTERPRETER $program
+    The status should be failure
+    The lines of stderr should eq 2
+    The line 2 of stderr should eq "$program:1:4: Unknown token: ~"
+  End
+End
+
+Describe 'lexer suggestions'
+  It "suggests STDOUT for stdout"
+    echo "1 + stdout;" >> $program
+    When call $U_INTERPRETER $program
+    The status should be failure
+    The line 2 of stderr should include "Unknown token: stdout (did you mean STDOUT?)"
+  End
+
+  It "suggests a variable for a bare word"
+    echo "1 + \$x;" >> $program
+    echo "x + STDOUT;" >> $program
+    When call $U_INTERPRETER $program
+    The status should be failure
+    The line 2 of stderr should include "did you mean 'x' or \$x?"
+  End
+
+  It "suggests a string for a multi-character letter"
+    echo "'ab' + STDOUT;" >> $program
+    When call $U_INTERPRETER $program
+    The status should be failure
+    The line 2 of stderr should include "Unknown token: 'ab'"
+    The line 2 of stderr should include 'did you mean "ab"?'
+  End
+
+  It "suggests := for a lone colon"
+    echo "@f : +;" >> $program
+    When call $U_INTERPRETER $program
+    The status should be failure
+    The line 2 of stderr should include "Unknown token: : (did you mean :=?)"
+  End
+
+  It "reports an unclosed string"
+    echo '"hello + STDOUT;' >> $program
+    echo '1 + STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+    The status should be failure
+    The lines of stderr should eq 2
+    The line 2 of stderr should include "1:1: Unterminated string, missing closing quote"
+  End
+
+  It "reports a repeater missing its closing brace"
+    echo "1 {5 + STDOUT;" >> $program
+    When call $U_INTERPRETER $program
+    The status should be failure
+    The lines of stderr should eq 2
+    The line 2 of stderr should include "1:3: Unclosed repeater, missing closing }: {5"
+  End
+
+  It "reports an unclosed block comment"
+    echo "1 + STDOUT; /* no end" >> $program
+    When call $U_INTERPRETER $program
+    The status should be failure
+    The line 2 of stderr should include "1:13: Unterminated block comment"
+  End
+End
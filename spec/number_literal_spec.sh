@@ -0,0 +1,87 @@
+#shellcheck shell=sh
+Describe 'integer literals'
+  Parameters
+    '0xFF' '255'
+    '0x1f' '31'
+    '-0x10' '-16'
+    '0b1010' '10'
+    '0o17' '15'
+    '1_000_000' '1000000'
+    '0b1111_0000' '240'
+    '-9223372036854775808' '-9223372036854775808'
+  End
+
+  It "parses [$1] as $2"
+    echo "$1 STDOUT;" >> $program
+    When call $U_INTERPRETER $program
+    The stdout should eq "$2"
+    The status should be success
+  End
+End
+
+Describe 'float literals'
+  Parameters
+    '1e6' '1000000'
+    '2.5e-3' '0.0025'
+    '1.5E+2' '150'
+    '1_000.25' '1000.25'
+    'inf' 'inf'
+    '-inf' '-inf'
+    'nan' 'NaN'
+  End
+
+  It "parses [$1] as $2"
+    echo "$1 STDOUT;" >> $program
+    When call $U_INTERPRETER $program
+    The stdout should eq "$2"
+    The status should be success
+  End
+End
+
+Describe 'numeric literals with operators'
+  It 'can increment a hex literal'
+    echo "0xFF + STDOUT;" >> $program
+    When call $U_INTERPRETER $program
+    The stdout should eq '256'
+    The status should be success
+  End
+
+  It 'can decrement a float with an exponent'
+    echo "1e3 - STDOUT;" >> $program
+    When call $U_INTERPRETER $program
+    The stdout should eq '999'
+    The status should be success
+  End
+
+  It 'can be followed by a keyword without a space'
+    echo "5STDOUT; 1.5STDOUT;" >> $program
+    When call $U_INTERPRETER $program
+    The line 1 of stdout should eq '5'
+    The line 2 of stdout should eq '1.5'
+    The status should be success
+  End
+End
+
+Describe 'malformed numeric literals'
+  Parameters
+    '0xZZ' 'Malformed integer literal'
+    '0x' 'Malformed integer literal'
+    '0b102' 'Malformed integer literal'
+    '1_' 'Malformed integer literal'
+    '12abc' 'Malformed integer literal'
+    '1e' 'Malformed float literal'
+    '1.5.3' 'Malformed float literal'
+    '99999999999999999999' 'Integer literal out of range'
+    '1e999' 'Float literal out of range'
+  End
+
+  It "reports [$1] as a lexical error"
+    echo "$1 + STDOUT;" >> $program
+    When call $U_INTERPRETER $program
+    The stdout should be blank
+    The status should be failure
+    The lines of stderr should eq 2
+    The line 1 of stderr should eq "Lexical analysis failed!"
+    The line 2 of stderr should include "$2: $1"
+  End
+End
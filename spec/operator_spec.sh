@@ -0,0 +1,118 @@
+#shellcheck shell=sh
+Describe '@ named operators'
+  It 'can be defined and then used like a built-in operator'
+    echo '@rot13 := {13} +; "hello" @rot13 STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The stdout should eq 'uryyb'
+  End
+
+  It 'can be used inside repeaters and groups'
+    echo '@twice := + +; 0 {3} @twice STDOUT; 0 (@twice -) STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The first line of stdout should eq '6'
+    The second line of stdout should eq '1'
+  End
+
+  It 'can be composed from other named operators'
+    echo '@twice := + +; @four := @twice @twice; 0 @four {2} @four STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The stdout should eq '12'
+  End
+
+  It 'run backwards under a negative repeat count'
+    echo '@show := STDOUT + STDOUT; 0 {-1} @show;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The first line of stdout should eq '0'
+    The second line of stdout should eq '-1'
+  End
+
+  It 'are kept apart from variables with the same name'
+    echo '2 $x; @x := {$x} +; 1 @x STDOUT; $x STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The first line of stdout should eq '3'
+    The second line of stdout should eq '2'
+  End
+
+  It 'must be defined before they are used'
+    echo '0 @later STDOUT; @later := +;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The first line of stderr should eq 'Syntax analysis failed!'
+    The line 2 of stderr should include '1:3: Operator not defined: @later'
+  End
+
+  It 'can call themselves'
+    echo '@double := ?zero( | - @double + +); 5 @double STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The stdout should eq '10'
+  End
+
+  It 'stop with a runtime error when they recurse too deeply'
+    echo '@loop := + @loop; 0 @loop STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The stdout should eq ''
+    The first line of stderr should eq 'Execution failed!'
+    The line 2 of stderr should include '1:12: Too many nested operator calls (more than 1000): @loop'
+  End
+
+  Describe 'nesting operators deeply inside a recursive operator'
+    Parameters
+      '(' ')'
+      '{1} ' ''
+      '?zero(|' ')'
+      '{until fixed} ' ''
+    End
+
+    It "stop with a runtime error rather than crashing, like $1"
+      printf '@f := %s@f%s; 1 @f;\n' "$(printf "%.0s$1" $(seq 255))" "$(printf "%.0s$2" $(seq 255))" >> $program
+      When call $U_INTERPRETER $program
+
+      The status should be failure
+      The stdout should eq ''
+      The first line of stderr should eq 'Execution failed!'
+      The line 2 of stderr should include 'Operators nested too deeply while running (more than 16000 levels)'
+    End
+  End
+
+  It 'cannot be defined twice'
+    echo '@f := +; @f := -;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The first line of stderr should eq 'Syntax analysis failed!'
+    The line 2 of stderr should include '1:10: Cannot define this operator twice: @f (first defined at 1:1)'
+  End
+
+  It 'cannot assign variables'
+    echo '@f := (+ $x);' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The first line of stderr should eq 'Syntax analysis failed!'
+    The line 2 of stderr should include '1:10: Cannot assign to a variable inside an operator definition: $x'
+  End
+
+  It 'need a := after their name'
+    echo '@f + +;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The first line of stderr should eq 'Syntax analysis failed!'
+    The line 2 of stderr should include "1:4: Unexpected token: Plus '+', expected :="
+  End
+End
@@ -91,12 +91,49 @@ Describe '{} repeater'
     The first line of stdout should eq '120'
   End
 
-  It 'returns a lexer error when used with a negative number'
-    echo '0 {-1} + STDOUT;' >> $program
+  It 'applies the inverse operator when the count is negative'
+    echo '10 {-3} + STDOUT;' >> $program
     When call $U_INTERPRETER $program
 
-    The status should be failure
-    The first line of stderr should eq 'Lexical analysis failed!'
+    The status should be success
+    The first line of stdout should eq '7'
+  End
+
+  It 'prints as many times as the size of a negative count'
+    echo '5 {-2} STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The line 1 of stdout should eq '5'
+    The line 2 of stdout should eq '5'
+    The lines of stdout should eq 2
+  End
+
+  It 'multiplies the signs of nested counts'
+    echo '0 {-2} {-3} + STDOUT; 0 {2} {-3} + STDOUT; 0 {-2} {3} - STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The line 1 of stdout should eq '6'
+    The line 2 of stdout should eq '-6'
+    The line 3 of stdout should eq '6'
+  End
+
+  It 'runs an inverted group backwards'
+    echo '0 {-1} (+ STDOUT + +) STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The line 1 of stdout should eq '-2'
+    The line 2 of stdout should eq '-3'
+  End
+
+  It 'undoes a repeat with the opposite count'
+    echo '"Hello" {5} (+ +) {-5} (+ +) STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The first line of stdout should eq 'Hello'
   End
 
   It 'returns a syntax error at the end of a line'
@@ -108,4 +145,57 @@ Describe '{} repeater'
     The line 2 of stderr should include 'Unexpected token'
     The line 2 of stderr should include ';'
   End
+
+  It 'returns a syntax error when the count is too large'
+    echo '0 {99999999999} + STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The first line of stderr should eq 'Syntax analysis failed!'
+    The line 2 of stderr should include '1:3: Repeat count out of range, expected between -4294967295 and 4294967295'
+  End
+
+  It 'can take its count from an integer variable'
+    echo '3 $x; 4 $y; 0 {$y} {$x} + STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The first line of stdout should eq '12'
+  End
+
+  It 'returns a syntax error when the count variable is not declared'
+    echo '0 {$n} + STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The first line of stderr should eq 'Syntax analysis failed!'
+    The line 2 of stderr should include '1:4: Variable not declared: $n'
+  End
+
+  It 'returns a runtime error when the count variable is not an integer'
+    echo '1.5 $n; 0 {$n} + STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The stdout should eq ''
+    The first line of stderr should eq 'Execution failed!'
+    The line 2 of stderr should include '1:12: Repeat count must be an Integer, but $n is a Float'
+  End
+
+  It 'applies the inverse operator when the count variable is negative'
+    echo '-2 $n; 10 {$n} + STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The first line of stdout should eq '8'
+  End
+
+  It 'suggests a variable when the count is a bare name'
+    echo '0 {n} + STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The first line of stderr should eq 'Lexical analysis failed!'
+    The line 2 of stderr should include 'did you mean {$n}?'
+  End
 End
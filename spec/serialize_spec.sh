@@ -0,0 +1,175 @@
+#shellcheck shell=sh
+Describe '--dump-tokens'
+  It 'prints the tokens as JSON'
+    echo '1 +;' >> $program
+    When call $U_INTERPRETER --dump-tokens $program
+
+    The status should be success
+    The line 1 of stdout should eq '['
+    The stdout should include '"name": "Integer"'
+    The stdout should include '"name": "Plus"'
+    The stdout should include '"value": ";"'
+  End
+
+  It 'prints the tokens as an S-expression with --sexpr'
+    echo '1 +;' >> $program
+    When call $U_INTERPRETER --dump-tokens --sexpr $program
+
+    The status should be success
+    The line 1 of stdout should eq '((:name "Integer" :value "1" :span (:start 0 :end 1 :line 1 :column 1))'
+    The line 3 of stdout should eq ' (:name "Plus" :value "+" :span (:start 2 :end 3 :line 1 :column 3))'
+  End
+
+  It 'returns the errors of a program that does not lex'
+    echo '1 ~;' >> $program
+    When call $U_INTERPRETER --dump-tokens $program
+
+    The status should be failure
+    The stdout should eq ''
+    The first line of stderr should eq 'Lexical analysis failed!'
+  End
+End
+
+Describe '--dump-ast'
+  It 'prints the syntax tree as JSON'
+    echo '"hi" STDOUT;' >> $program
+    When call $U_INTERPRETER --dump-ast $program
+
+    The status should be success
+    The stdout should include '"Chain": {'
+    The stdout should include '"UString": "hi"'
+    The stdout should include '"Stdout": {'
+  End
+
+  It 'prints the syntax tree as an S-expression with --sexpr'
+    echo '1 +;' >> $program
+    When call $U_INTERPRETER --dump-ast --sexpr $program
+
+    The status should be success
+    The line 1 of stdout should eq '(:statements ((:Chain (:source (:Literal ((:Integer 1) (:start 0 :end 1 :line 1 :column 1)))'
+    The line 2 of stdout should eq '                       :ops ((:Inc (:start 2 :end 3 :line 1 :column 3)))'
+  End
+
+  It 'does not run the program'
+    echo '1 STDOUT;' >> $program
+    When call $U_INTERPRETER --dump-ast $program
+
+    The status should be success
+    The line 1 of stdout should eq '{'
+  End
+
+  It 'returns the errors of a program that does not resolve'
+    echo '$x STDOUT;' >> $program
+    When call $U_INTERPRETER --dump-ast $program
+
+    The status should be failure
+    The stdout should eq ''
+    The first line of stderr should eq 'Syntax analysis failed!'
+    The line 2 of stderr should include '1:1: Variable not declared: $x'
+  End
+End
+
+Describe '--load-ast'
+  It 'runs a syntax tree printed as JSON'
+    dump_and_load() { $U_INTERPRETER --dump-ast "$program" > "$program.json" && $U_INTERPRETER --load-ast "$program.json"; }
+    echo '"hello" {13} + STDOUT; @f := ?zero(+ | {until 10} -); 0 @f STDOUT; 20 @f STDOUT;' >> $program
+    When call dump_and_load
+
+    The status should be success
+    The line 1 of stdout should eq 'uryyb'
+    The line 2 of stdout should eq '1'
+    The line 3 of stdout should eq '10'
+  End
+
+  It 'runs a syntax tree printed as an S-expression'
+    dump_and_load() { $U_INTERPRETER --dump-ast --sexpr "$program" > "$program.sexpr" && $U_INTERPRETER --load-ast "$program.sexpr"; }
+    echo "'a' MUT \$x; BEGIN; 'q' SET \$x; 1.5 {2} + STDOUT; END; \$x STDOUT;" >> $program
+    When call dump_and_load
+
+    The status should be success
+    The line 1 of stdout should eq '3.5'
+    The line 2 of stdout should eq 'q'
+  End
+
+  It 'keeps floats that JSON has no numbers for'
+    dump_and_load() { $U_INTERPRETER --dump-ast "$program" > "$program.json" && $U_INTERPRETER --load-ast "$program.json"; }
+    echo '-inf STDOUT; 0.1 STDOUT;' >> $program
+    When call dump_and_load
+
+    The status should be success
+    The line 1 of stdout should eq '-inf'
+    The line 2 of stdout should eq '0.1'
+  End
+
+  It 'runs a syntax tree without spans'
+    echo '{"statements": [{"Chain": {"source": {"Literal": [{"Integer": 41}, {}]}, "ops": [{"Inc": {}}, {"Stdout": {}}], "span": {}}}]}' >> $program
+    When call $U_INTERPRETER --load-ast $program
+
+    The status should be success
+    The stdout should eq '42'
+  End
+
+  It 'imports files relative to the syntax tree'
+    echo '@double := + +;' > "$program_dir/serialize_lib.u"
+    echo '(:statements ((:Import (:path "serialize_lib.u" :alias "lib" :path_span () :alias_span () :span ()))' >> $program
+    echo '              (:Chain (:source (:Literal ((:Integer 1) ())) :ops ((:Call ("@lib.double" ())) (:Stdout ())) :span ()))))' >> $program
+    When call $U_INTERPRETER --load-ast $program
+
+    The status should be success
+    The stdout should eq '3'
+  End
+
+  It 'returns an error for a syntax tree that is not valid JSON'
+    echo '{"statements": [' >> $program
+    When call $U_INTERPRETER --load-ast $program
+
+    The status should be failure
+    The first line of stderr should eq 'Reading the syntax tree failed!'
+    The line 2 of stderr should include '2:0: EOF while parsing a list'
+  End
+
+  It 'returns an error for a syntax tree that is not a valid S-expression'
+    echo '(:statements ((:Begin (:span ())) bogus))' >> $program
+    When call $U_INTERPRETER --load-ast $program
+
+    The status should be failure
+    The first line of stderr should eq 'Reading the syntax tree failed!'
+    The line 2 of stderr should include '1:35: expected a value, found bogus'
+  End
+
+  It 'returns an error for a syntax tree that is nested too deeply, rather than crashing'
+    printf '%s%s\n' "$(printf '%.0s(' $(seq 100000))" "$(printf '%.0s)' $(seq 100000))" >> $program
+    When call $U_INTERPRETER --load-ast $program
+
+    The status should be failure
+    The first line of stderr should eq 'Reading the syntax tree failed!'
+    The line 2 of stderr should include '1:1041: nested more than 1040 deep'
+  End
+
+  It 'runs the syntax tree of a program nested as deeply as it may be'
+    dump_and_load() { $U_INTERPRETER --dump-ast "$program" > "$program.json" && $U_INTERPRETER --load-ast "$program.json"; }
+    printf '1 %s+%s STDOUT;\n' "$(printf '%.0s{1} (' $(seq 128))" "$(printf '%.0s)' $(seq 128))" >> $program
+    When call dump_and_load
+
+    The status should be success
+    The stdout should eq '2'
+  End
+
+  It 'returns an error for a syntax tree with an unknown statement'
+    echo '{"statements": [{"Loop": {}}]}' >> $program
+    When call $U_INTERPRETER --load-ast $program
+
+    The status should be failure
+    The first line of stderr should eq 'Reading the syntax tree failed!'
+    The line 2 of stderr should include 'unknown variant `Loop`'
+  End
+
+  It 'returns the errors of a syntax tree that does not resolve'
+    echo '(:statements ((:Chain (:source (:Var ("$x" (:line 3 :column 5))) :ops () :span ()))))' >> $program
+    When call $U_INTERPRETER --load-ast $program
+
+    The status should be failure
+    The first line of stderr should eq 'Syntax analysis failed!'
+    The line 2 of stderr should include '3:5: Variable not declared: $x'
+  End
+End
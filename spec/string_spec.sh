@@ -113,10 +113,132 @@ Describe 'strings'
     End
   End
 
+  Describe 'Strings with digits and punctuation'
+    It 'can be rot13-ciphered, leaving punctuation unchanged'
+      echo '"Hello, world!" {13} + STDOUT;' >> $program
+      When call $U_INTERPRETER $program
+      The status should be success
+      The stdout should eq "Uryyb, jbeyq!"
+    End
+
+    It 'cycle their digits from 0 to 9'
+      echo '"Agent 007, room 29B." {3} + STDOUT;' >> $program
+      When call $U_INTERPRETER $program
+      The status should be success
+      The stdout should eq "Djhqw 330, urrp 52E."
+    End
+
+    It 'can be deciphered with the inverse operator'
+      echo '"It costs $4.99 (or 5€)!" {8} + {8} - STDOUT;' >> $program
+      When call $U_INTERPRETER $program
+      The status should be success
+      The stdout should eq 'It costs $4.99 (or 5€)!'
+    End
+  End
+
   It 'can be one character long'
     echo '"A" + STDOUT;' >> $program
     When call $U_INTERPRETER $program
     The status should be success
     The stdout should eq "B"
   End
+
+  Describe 'empty strings'
+    It 'can be printed to STDOUT'
+      echo '"" STDOUT;' >> $program
+      When call $U_INTERPRETER $program
+      The status should be success
+      The stdout should eq ""
+    End
+
+    It 'can be incremented'
+      echo '"" + STDOUT;' >> $program
+      When call $U_INTERPRETER $program
+      The status should be success
+      The stdout should eq ""
+    End
+  End
+
+  Describe 'escape sequences'
+    It 'can contain an escaped quote'
+      printf '%s\n' '"say \"hi\"" STDOUT;' >> $program
+      When call $U_INTERPRETER $program
+      The status should be success
+      The stdout should eq 'say "hi"'
+    End
+
+    It 'can contain an escaped backslash'
+      printf '%s\n' '"a\\b" STDOUT;' >> $program
+      When call $U_INTERPRETER $program
+      The status should be success
+      The stdout should eq 'a\b'
+    End
+
+    It 'can contain newlines and tabs'
+      printf '%s\n' '"a\nb\tc" STDOUT;' >> $program
+      When call $U_INTERPRETER $program
+      The status should be success
+      The lines of stdout should eq 2
+      The line 1 of stdout should eq 'a'
+      The line 2 of stdout should eq "b	c"
+    End
+
+    It 'can contain unicode escapes'
+      printf '%s\n' '"\u{48}\u{3bb}" STDOUT;' >> $program
+      When call $U_INTERPRETER $program
+      The status should be success
+      The stdout should eq 'Hλ'
+    End
+
+    It 'keeps whitespace escapes unchanged when incremented'
+      printf '%s\n' '"a\tb" + STDOUT;' >> $program
+      When call $U_INTERPRETER $program
+      The status should be success
+      The stdout should eq "b	c"
+    End
+
+    It 'reports an unknown escape sequence as a lexical error'
+      printf '%s\n' '"a\qb" STDOUT;' >> $program
+      When call $U_INTERPRETER $program
+      The status should be failure
+      The line 1 of stderr should eq 'Lexical analysis failed!'
+      The line 2 of stderr should include 'Invalid escape sequence'
+    End
+
+    It 'reports an invalid unicode escape as a lexical error'
+      printf '%s\n' '"\u{d800}" STDOUT;' >> $program
+      When call $U_INTERPRETER $program
+      The status should be failure
+      The line 1 of stderr should eq 'Lexical analysis failed!'
+      The line 2 of stderr should include 'Invalid unicode escape'
+    End
+  End
+
+  Describe 'multi-line strings'
+    It 'can span several lines'
+      echo '"Hello' >> $program
+      echo 'world" + STDOUT;' >> $program
+      When call $U_INTERPRETER $program
+      The status should be success
+      The lines of stdout should eq 2
+      The line 1 of stdout should eq 'Ifmmp'
+      The line 2 of stdout should eq 'xpsme'
+    End
+  End
+
+  Describe 'raw strings'
+    It 'do not process escape sequences'
+      printf '%s\n' 'r"C:\new\table" STDOUT;' >> $program
+      When call $U_INTERPRETER $program
+      The status should be success
+      The stdout should eq 'C:\new\table'
+    End
+
+    It 'can contain quotes when delimited with #'
+      printf '%s\n' 'r#"she said "hi""# STDOUT;' >> $program
+      When call $U_INTERPRETER $program
+      The status should be success
+      The stdout should eq 'she said "hi"'
+    End
+  End
 End
@@ -50,3 +50,86 @@ Describe 'syntax analyzer errors'
     The line 2 of stderr should include "Expected more tokens before end of line"
   End
 End
+
+Describe 'syntax error locations'
+  It "reports the file, line and column of an unexpected token"
+    echo "1 + STDOUT;" >> $program
+    echo "2 + 1 STDOUT;" >> $program
+    When call $U_INTERPRETER $program
+    The status should be failure
+    The line 2 of stderr should start with "$program:2:5:"
+    The line 2 of stderr should include "Unexpected token"
+  End
+
+  It "reports where an incomplete line starts"
+    echo "1 + STDOUT;" >> $program
+    echo "   5 +" >> $program
+    When call $U_INTERPRETER $program
+    The status should be failure
+    The line 2 of stderr should start with "$program:2:4:"
+  End
+End
+
+Describe 'syntax error recovery'
+  It "lists the tokens that could start a statement"
+    echo "+ 1 STDOUT;" >> $program
+    When call $U_INTERPRETER $program
+    The status should be failure
+    The line 2 of stderr should include "expected a letter, an integer, a float, a string, \$var, @op, BEGIN, END or IMPORT"
+  End
+
+  It "lists the tokens that could follow an operator"
+    echo "1 + 1 + STDOUT;" >> $program
+    When call $U_INTERPRETER $program
+    The status should be failure
+    The line 2 of stderr should include "Unexpected token: Integer '1', expected +, -, STDOUT, {n}, {until x}, (, ?zero, \$var, MUT, SET, @op or ;"
+  End
+
+  It "lists the tokens that could follow a repeater"
+    echo "0 {2} \$x;" >> $program
+    When call $U_INTERPRETER $program
+    The status should be failure
+    The line 2 of stderr should include "expected +, -, STDOUT, {n}, {until x}, (, ?zero or @op"
+  End
+
+  It "lists the tokens that could end an incomplete line"
+    echo "1 +" >> $program
+    When call $U_INTERPRETER $program
+    The status should be failure
+    The line 2 of stderr should include "Expected more tokens before end of line, expected +, -, STDOUT, {n}, {until x}, (, ?zero, \$var, MUT, SET, @op or ;"
+  End
+
+  It "reports a missing semicolon once, where the next statement starts"
+    echo "1 + STDOUT" >> $program
+    echo "2 + STDOUT;" >> $program
+    echo "3 + STDOUT;" >> $program
+    When call $U_INTERPRETER $program
+    The status should be failure
+    The lines of stderr should eq 2
+    The line 2 of stderr should start with "$program:2:1: Unexpected token: Integer '2'"
+  End
+
+  It "reports one error per malformed statement"
+    echo "1 + + 1 + 2 + 3;" >> $program
+    echo "'a' STDOUT;" >> $program
+    echo "+ 3 4 5;" >> $program
+    echo '"x" {2} 5 6;' >> $program
+    When call $U_INTERPRETER $program
+    The status should be failure
+    The lines of stderr should eq 4
+    The line 2 of stderr should start with "$program:1:7:"
+    The line 3 of stderr should start with "$program:3:1:"
+    The line 4 of stderr should start with "$program:4:9:"
+  End
+
+  It "keeps checking statements after a malformed one"
+    echo "1 1 \$x;" >> $program
+    echo "2 \$y;" >> $program
+    echo "\$z STDOUT;" >> $program
+    When call $U_INTERPRETER $program
+    The status should be failure
+    The lines of stderr should eq 3
+    The line 2 of stderr should start with "$program:1:3:"
+    The line 3 of stderr should include "Variable not declared: \$z"
+  End
+End
@@ -0,0 +1,116 @@
+#shellcheck shell=sh
+Describe '{until x} loops'
+  It 'repeat until the value equals a literal'
+    echo '0 {until 3} (+ STDOUT);' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The lines of stdout should eq 3
+    The line 3 of stdout should eq '3'
+  End
+
+  It 'repeat until the value equals a variable'
+    echo "'e' \$target; 'a' {until \$target} + STDOUT;" >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The stdout should eq 'e'
+  End
+
+  It 'do nothing if the value is already there'
+    echo '4.5 {until 4.5} (+ STDOUT) STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The stdout should eq '4.5'
+  End
+
+  It 'repeat until a fixed point'
+    # Counts down to zero, which ?zero leaves alone.
+    echo '7 {until fixed} ?zero( | -) STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The stdout should eq '0'
+  End
+
+  It 'apply the inverse operator under a negative count'
+    echo '5 {-1} {until 2} + STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The stdout should eq '2'
+  End
+
+  It 'return a runtime error when they hit the default iteration limit'
+    echo '0 {until -1} + STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The stdout should eq ''
+    The first line of stderr should eq 'Execution failed!'
+    The line 2 of stderr should include '1:3: Loop did not reach its target within 10000 iterations'
+  End
+
+  It 'take their iteration limit from max'
+    echo '0 {until 10 max 5} + STDOUT; 0 {until 5 max 5} + STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The first line of stderr should eq 'Execution failed!'
+    The line 2 of stderr should include '1:3: Loop did not reach its target within 5 iterations'
+  End
+
+  It 'count applying the operator that finds the fixed point towards max'
+    echo '2 {until fixed max 3} ?zero( | -) STDOUT;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be success
+    The stdout should eq '0'
+  End
+
+  It 'return a syntax error when the target has the wrong type'
+    echo '0 {until 1.5} +;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The first line of stderr should eq 'Syntax analysis failed!'
+    The line 2 of stderr should include '1:3: Loop target has type Float, so a value of type Integer can never reach it'
+  End
+
+  It 'return a syntax error when the target variable is not declared'
+    echo '0 {until $x} +;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The first line of stderr should eq 'Syntax analysis failed!'
+    The line 2 of stderr should include '1:10: Variable not declared: $x'
+  End
+
+  It 'count the column of the target variable in characters'
+    echo "'λ' {until \$é} +;" >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The first line of stderr should eq 'Syntax analysis failed!'
+    The line 2 of stderr should include '1:12: Variable not declared: $é'
+  End
+
+  It 'return a syntax error for a malformed target'
+    echo '0 {until x} +;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The first line of stderr should eq 'Syntax analysis failed!'
+    The line 2 of stderr should include '1:3: Malformed loop target, expected $var, a number or fixed: x'
+  End
+
+  It 'return a lexer error without a target'
+    echo '0 {until} +;' >> $program
+    When call $U_INTERPRETER $program
+
+    The status should be failure
+    The first line of stderr should eq 'Lexical analysis failed!'
+    The line 2 of stderr should include 'Unknown token: {until}'
+  End
+End
@@ -75,5 +75,24 @@ Describe 'variables'
       The stdout should eq '9'
       The status should be success
     End
+
+    It 'can contain digits after the first character'
+      echo '5 $x1;' >> $program
+      echo '$x1 STDOUT;' >> $program
+      When call $U_INTERPRETER $program
+      The stdout should eq '5'
+      The status should be success
+    End
+
+    It 'can contain unicode letters'
+      echo '5 $größe;' >> $program
+      echo '6 $λ;' >> $program
+      echo '$größe STDOUT;' >> $program
+      echo '$λ STDOUT;' >> $program
+      When call $U_INTERPRETER $program
+      The line 1 of stdout should eq '5'
+      The line 2 of stdout should eq '6'
+      The status should be success
+    End
   End
 End
@@ -18,6 +18,15 @@ Describe 'variables'
       The line 1 of stderr should include 'Syntax analysis failed'
       The line 2 of stderr should include '$xyz'
     End
+
+    It 'reports where the variable was first assigned'
+      echo '5 $xyz;' >> $program
+      echo '8 $xyz;' >> $program
+      When call $U_INTERPRETER $program
+      The status should be failure
+      The line 2 of stderr should start with "$program:2:3:"
+      The line 2 of stderr should include 'first assigned at 1:3'
+    End
   End
 
   Describe 'must be assigned'
@@ -39,12 +48,19 @@ Describe 'variables'
       The stderr should include 'Lexical analysis failed'
     End
 
-    It 'cannot contain numbers'
+    It 'cannot start with a number'
       echo '5 + $6;' >> $program
       echo '$6 STDOUT;' >> $program
       When call $U_INTERPRETER $program
       The status should be failure
       The stderr should include 'Lexical analysis failed'
     End
+
+    It 'cannot contain a pipe'
+      echo '5 + $my|variable;' >> $program
+      When call $U_INTERPRETER $program
+      The status should be failure
+      The stderr should include 'Lexical analysis failed'
+    End
   End
 End